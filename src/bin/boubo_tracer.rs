@@ -7,19 +7,25 @@ use std::{
 
 use anyhow::bail;
 use boubo_trace::{
+    redact::RedactArgs,
+    summary::TraceSummary,
     syscall::{
-        SyscallNewTypeError, SyscallIter, TraceData, SyscallIterOpts
-        // new_types::{NewTypeError, sockaddr_ser},
+        SyscallIterOpts,
+        capture::CaptureLimits,
+        filter::SyscallFilter,
+        inject::{Delay, Fault, Tamper},
+        replay::Replay,
     },
-    tracee::Tracee,
+    trace_file::{self, TraceFile},
+    tracee::window::TraceWindow,
+    tracer::Tracer,
 };
 use clap::Parser;
-use log::{LevelFilter, debug, info};
-use nix::unistd::Pid;
-use spawn_ptrace::CommandPtraceSpawn;
+use log::{LevelFilter, info};
 
 #[derive(Parser)]
 #[command(version, about)]
+#[expect(clippy::struct_excessive_bools, reason = "these are CLI flags")]
 struct Args {
     /// Save captured syscalls to <FILE>
     #[arg(long, short)]
@@ -30,6 +36,81 @@ struct Args {
     /// Don't skip syscalls called before main in captured process
     #[arg(long)]
     no_skip_to_main: bool,
+    /// Also skip syscalls called before main in programs executed by captured process
+    #[arg(long)]
+    skip_to_main_after_exec: bool,
+    /// Print count, errors and time spent in each syscall after the traced process exits
+    #[arg(long, short)]
+    summary: bool,
+    /// Only trace syscalls matching <EXPR>, other syscalls are skipped without being parsed
+    ///
+    /// <EXPR> is a comma separated list of syscall names, numbers and classes (%file, %network,
+    /// %process, %memory, %signal and %ipc). Prefix it with `!` to trace everything except the
+    /// listed syscalls, e.g. `-e '!read,write'`.
+    #[arg(long = "trace", short = 'e', value_name = "EXPR")]
+    filter: Option<SyscallFilter>,
+    /// Use a seccomp filter to stop the traced process only on syscalls selected by --trace
+    ///
+    /// This makes tracing with a filter much faster, but set-user-ID binaries will not gain
+    /// privileges. execve and execveat can't be traced this way, --trace must not select them,
+    /// without --trace everything else is traced.
+    #[arg(long)]
+    seccomp: bool,
+    /// Record realtime (Unix epoch) timestamps of syscalls, to correlate them with other logs
+    #[arg(long)]
+    realtime: bool,
+    /// Capture up to <DEPTH> return addresses of callers of every syscall
+    ///
+    /// Only code compiled with frame pointers can be walked through.
+    #[arg(long, value_name = "DEPTH", default_value_t = 0)]
+    stack_depth: usize,
+    /// Only trace syscalls made while <FUNCTION> is running, can be repeated
+    #[arg(long, value_name = "FUNCTION")]
+    window: Vec<String>,
+    /// Make syscalls matching <RULE> fail without running them, can be repeated
+    ///
+    /// <RULE> is a syscall list like in --trace, followed by `:error=ERRNO` and optionally by
+    /// `:path=GLOB` or `:fd=FD|GLOB` matching path or file descriptor arguments, `:when=N` to fail
+    /// only the Nth matching syscall and `:probability=P` with `:seed=N`, e.g.
+    /// `openat:error=ENOENT:path=/etc/config:when=3` or `write:error=EAGAIN:fd=socket:*`.
+    #[arg(long, value_name = "RULE")]
+    inject: Vec<Fault>,
+    /// Hold the tracee at syscalls matching <RULE> to simulate latency, can be repeated
+    ///
+    /// <RULE> is like in --inject, with `:delay=DURATION` instead of `:error=`, e.g. `200ms`, and
+    /// optionally `:at=exit` to hold the tracee after the syscall instead of before it, e.g.
+    /// `recvfrom:delay=200ms:fd=3`. Delays are not counted in the syscall durations.
+    #[arg(long, value_name = "RULE")]
+    delay: Vec<Delay>,
+    /// Rewrite results of successful syscalls matching <RULE>, can be repeated
+    ///
+    /// <RULE> is like in --inject, with one of `:return=N` replacing the return value,
+    /// `:truncate=N` returning at most N bytes or `:content=TEXT` filling the read buffer,
+    /// `:content=@FILE` fills it from FILE, e.g. `getpid:return=42` or
+    /// `read:fd=/etc/resolv.conf:content=@resolv.conf`.
+    #[arg(long, value_name = "RULE")]
+    tamper: Vec<Tamper>,
+    /// Feed results of `read`, `recvfrom`, `getrandom` and `clock_gettime` recorded in trace
+    /// <FILE> back to the program, instead of running them
+    ///
+    /// The first syscall which differs from the recording is reported and ends the replay. The
+    /// recording has to trace the same syscalls, e.g. with the same --trace.
+    #[arg(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
+    /// Capture at most <BYTES> of each buffer, like data of read and write
+    #[arg(long, value_name = "BYTES")]
+    max_buffer: Option<usize>,
+    /// Capture at most <BYTES> of each string, like paths
+    #[arg(long, value_name = "BYTES")]
+    max_string: Option<usize>,
+    /// Capture at most <BYTES> in total from all buffers and strings of a single syscall
+    #[arg(long, value_name = "BYTES")]
+    max_syscall_data: Option<usize>,
+    /// Don't capture any buffers or strings, only their lengths are recorded
+    #[arg(long, conflicts_with_all = ["max_buffer", "max_string", "max_syscall_data"])]
+    no_data: bool,
+    #[command(flatten)]
+    redact: RedactArgs,
     /// Working directory for <EXECUTABLE>
     ///
     /// If not specified, the current working directory is used.
@@ -98,41 +179,120 @@ impl App {
         App { args }
     }
 
+    fn capture_limits(&self) -> CaptureLimits {
+        if self.args.no_data {
+            return CaptureLimits::NO_DATA;
+        }
+        let default = CaptureLimits::default();
+        CaptureLimits {
+            max_buffer: self.args.max_buffer.unwrap_or(default.max_buffer),
+            max_string: self.args.max_string.unwrap_or(default.max_string),
+            max_per_syscall: self
+                .args
+                .max_syscall_data
+                .unwrap_or(default.max_per_syscall),
+        }
+    }
+
     pub fn call_cmd(&self) -> Result<(), anyhow::Error> {
+        // invalid patterns have to be reported before the process is spawned
+        let redactor = if self.args.redact.is_empty() {
+            None
+        } else {
+            Some(self.args.redact.redactor()?)
+        };
+        let replay = match &self.args.replay {
+            Some(path) => {
+                let bytes = std::fs::read(path)?;
+                let file = trace_file::from_bytes(&bytes)?;
+                Some(Replay::new(&file.trace))
+            }
+            None => None,
+        };
         let mut cmd = Command::new(canonicalize(&self.args.executable)?);
         cmd.args(&self.args.args);
 
         if let Some(work_dir) = &self.args.work_dir {
             cmd.current_dir(work_dir);
         }
-        let process = cmd.spawn_ptrace()?;
+        let filter = match &self.args.filter {
+            Some(filter) => filter.clone(),
+            // the seccomp filter can't stop on exec
+            None if self.args.seccomp => "!execve,execveat".parse()?,
+            None => SyscallFilter::default(),
+        };
 
         let mut called_syscalls = vec![];
-        let pid = Pid::from_raw(process.id().cast_signed());
-        debug!("traced pid: {pid}");
+        let mut summary = TraceSummary::new();
 
-        let opts = SyscallIterOpts::default().skip_to_main(!self.args.no_skip_to_main);
+        let mut opts = SyscallIterOpts::default()
+            .skip_to_main(!self.args.no_skip_to_main)
+            .skip_to_main_after_exec(self.args.skip_to_main_after_exec)
+            .realtime_timestamps(self.args.realtime)
+            .filter(filter)
+            .seccomp(self.args.seccomp)
+            .capture_limits(self.capture_limits())
+            .stack_depth(self.args.stack_depth);
+        if let Some(redactor) = &redactor {
+            opts = opts.redact(redactor.clone());
+        }
+        for function in &self.args.window {
+            opts = opts.window(TraceWindow::Function(function.clone()));
+        }
+        for fault in &self.args.inject {
+            opts = opts.inject_fault(fault.clone());
+        }
+        for delay in &self.args.delay {
+            opts = opts.inject_delay(delay.clone());
+        }
+        for tamper in &self.args.tamper {
+            opts = opts.tamper(tamper.clone());
+        }
+        if let Some(replay) = replay {
+            opts = opts.replay(replay);
+        }
 
-        for call in SyscallIter::new(Tracee::new(pid), &opts)? {
+        let mut child = Tracer::command(cmd).options(opts).spawn()?;
+        for call in child.by_ref() {
             match call {
                 Ok(call) => {
                     info!("Event/Syscall: {call:?}");
-                    called_syscalls.push(call);
-                },
+                    if self.args.summary {
+                        summary.add(&call);
+                    }
+                    // there's no need to keep the syscalls around if they won't be saved
+                    if self.args.output.is_some() {
+                        called_syscalls.push(call);
+                    }
+                }
                 Err(err) => log::error!("Error while parsing: {err}"),
             }
         }
 
-        let bytes = rkyv::to_bytes::<SyscallNewTypeError>(&called_syscalls)?;
-
-        let res = rkyv::from_bytes::<Vec<TraceData>, SyscallNewTypeError>(&bytes)?;
-        assert_eq!(called_syscalls, res);
+        if self.args.summary {
+            eprint!("{summary}");
+        }
 
         if let Some(path) = &self.args.output {
             if path.exists() {
                 bail!("File {} already exists!", path.display())
             }
-            let bytes = rkyv::to_bytes::<SyscallNewTypeError>(&called_syscalls)?;
+            let mut metadata = child.metadata();
+            metadata.tracer_args = std::env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            if let Some(redactor) = &redactor {
+                redactor.redact_metadata(&mut metadata);
+            }
+            let trace = TraceFile {
+                metadata: Some(metadata),
+                trace: called_syscalls,
+            };
+            let bytes = trace_file::to_bytes(&trace)?;
+
+            let res = trace_file::from_bytes(&bytes)?;
+            assert_eq!(trace, res);
+
             let mut file = File::create(path)?;
             file.write_all(&bytes)?;
         }
@@ -1,13 +1,71 @@
-use std::{fmt::Display, fs::File, io::Read, os::unix::fs::MetadataExt, path::PathBuf};
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{Read, Write},
+    os::unix::{fs::MetadataExt, process::ExitStatusExt},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    time::Duration,
+};
 
 use ahash::AHashMap;
-use anyhow::bail;
-use boubo_trace::syscall::{SyscallInfo, SyscallNewTypeError, TraceData, parse_error::TraceEvent};
-use clap::Parser;
+use anyhow::{Context, bail};
+use boubo_trace::{
+    redact::RedactArgs,
+    summary::TraceSummary,
+    symbolize::Symbolizer,
+    syscall::{
+        CallSite, SyscallInfo, TraceData, inject::DelayPoint, parse_error::TraceEvent, table,
+    },
+    trace_file::{self, TraceFile, TraceMetadata},
+};
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+    #[arg(required = true)]
+    input_file: Option<PathBuf>,
+    /// Print count, errors and time spent in each syscall instead of file accesses
+    #[arg(long, short)]
+    summary: bool,
+    /// Print call sites and call stacks of syscalls as function+offset and source lines
+    #[arg(long, conflicts_with = "summary")]
+    symbolize: bool,
+    /// Also look for separate debug files by build-id in <DIR>/.build-id/
+    #[arg(long, value_name = "DIR", requires = "symbolize")]
+    debug_dir: Vec<PathBuf>,
+    /// Print what was traced, on which host and with which options, instead of file accesses
+    #[arg(long, conflicts_with_all = ["summary", "symbolize"])]
+    info: bool,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Remove secrets from a saved trace, so it can be shared
+    Redact(RedactTraceArgs),
+    /// Rewrite a trace saved by an older version in the current format
+    Upgrade(UpgradeArgs),
+}
+
+#[derive(clap::Args)]
+struct UpgradeArgs {
     input_file: PathBuf,
+    /// Save the upgraded trace to <OUTPUT>
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct RedactTraceArgs {
+    input_file: PathBuf,
+    /// Save the redacted trace to <OUTPUT>
+    #[arg(long, short)]
+    output: PathBuf,
+    #[command(flatten)]
+    redact: RedactArgs,
 }
 
 #[derive(Debug)]
@@ -27,50 +85,193 @@ impl Display for CpuTimeFormat {
     }
 }
 
+/// Unix time, formatted as a UTC date and time
+struct UtcFormat(Duration);
+
+impl Display for UtcFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        // days to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+        let z = secs / 86400 + 719_468;
+        let era = z / 146_097;
+        let doe = z % 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + u64::from(month <= 2);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+            secs / 3600 % 24,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+/// Syscall duration, formatted the same way as `strace -T` does
+struct DurationFormat(Duration);
+
+impl Display for DurationFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{:.6}>", self.0.as_secs_f64())
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    if !args.input_file.is_file() {
-        bail!("file {} does not exist", args.input_file.display())
+    match args.command {
+        Some(Commands::Redact(redact_args)) => return redact(&redact_args),
+        Some(Commands::Upgrade(upgrade_args)) => return upgrade(&upgrade_args),
+        None => {}
     }
-    let metadata = std::fs::metadata(&args.input_file)?;
+    let input_file = args.input_file.expect("input file is required by clap");
+    let TraceFile {
+        metadata,
+        trace: syscalls,
+    } = read_trace(&input_file)?;
+
+    if args.info {
+        print_info(metadata.as_ref());
+    } else if args.summary {
+        print!("{}", syscalls.iter().collect::<TraceSummary>());
+    } else {
+        let symbolizer = args.symbolize.then(|| {
+            args.debug_dir
+                .into_iter()
+                .fold(Symbolizer::new(), Symbolizer::debug_dir)
+        });
+        print_file_accesses(syscalls, symbolizer);
+    }
+    Ok(())
+}
+
+fn read_trace(path: &Path) -> anyhow::Result<TraceFile> {
+    if !path.is_file() {
+        bail!("file {} does not exist", path.display())
+    }
+    let metadata = std::fs::metadata(path)?;
     if metadata.size() > 1024 * 1024 * 1024 {
         eprintln!("File is larger than 1 GiB! This could take a lot of RAM to parse.");
     }
-    let mut file = File::open(&args.input_file)?;
+    let mut file = File::open(path)?;
     let mut buf = vec![];
     file.read_to_end(&mut buf)?;
-    let syscalls = rkyv::from_bytes::<Vec<TraceData>, SyscallNewTypeError>(&buf)?;
+    trace_file::from_bytes(&buf).with_context(|| format!("could not read trace {}", path.display()))
+}
+
+fn write_trace(path: &Path, trace: &TraceFile) -> anyhow::Result<()> {
+    if path.exists() {
+        bail!("File {} already exists!", path.display())
+    }
+    let bytes = trace_file::to_bytes(trace)?;
+    let mut file = File::create(path)?;
+    file.write_all(&bytes)?;
+    Ok(())
+}
+
+fn upgrade(args: &UpgradeArgs) -> anyhow::Result<()> {
+    let trace = read_trace(&args.input_file)?;
+    write_trace(&args.output, &trace)
+}
+
+fn redact(args: &RedactTraceArgs) -> anyhow::Result<()> {
+    let mut redactor = args.redact.redactor()?;
+
+    let mut file = read_trace(&args.input_file)?;
+    if let Some(metadata) = &mut file.metadata {
+        redactor.redact_metadata(metadata);
+    }
+    for call in &mut file.trace {
+        redactor.redact_data(call);
+    }
+
+    write_trace(&args.output, &file)
+}
 
+fn print_info(metadata: Option<&TraceMetadata>) {
+    let Some(metadata) = metadata else {
+        println!("The trace has no metadata, it was saved by an older version of boubo_tracer");
+        return;
+    };
+    let yes_no = |value| if value { "yes" } else { "no" };
+    let args: Vec<_> = metadata
+        .args
+        .iter()
+        .map(|arg| format!("'{}'", String::from_utf8_lossy(arg)))
+        .collect();
+    let executable = String::from_utf8_lossy(&metadata.executable);
+    let work_dir = String::from_utf8_lossy(&metadata.work_dir);
+    println!("Executable:        {executable}");
+    println!("Arguments:         {}", args.join(" "));
+    println!("Working directory: {work_dir}");
+    println!("Environment:       {} variables", metadata.env.len());
+    for entry in &metadata.env {
+        println!("  {}", String::from_utf8_lossy(entry));
+    }
+    println!("Started:           {}", UtcFormat(metadata.start_time));
+    println!("Hostname:          {}", metadata.hostname);
+    println!("Kernel:            {}", metadata.kernel_version);
+    println!("Architecture:      {}", metadata.arch);
+    println!("Skip to main:      {}", yes_no(metadata.skip_to_main));
+    let after_exec = yes_no(metadata.skip_to_main_after_exec);
+    println!("... after exec:    {after_exec}");
+    println!("Traced syscalls:   {}", metadata.filter);
+    println!("Seccomp:           {}", yes_no(metadata.seccomp));
+    let realtime = yes_no(metadata.realtime_timestamps);
+    println!("Realtime times:    {realtime}");
+    println!("Stack depth:       {}", metadata.stack_depth);
+    let limit = |bytes| match bytes {
+        usize::MAX => "unlimited".to_owned(),
+        bytes => format!("{bytes} bytes"),
+    };
+    let limits = metadata.capture_limits;
+    println!("Buffer limit:      {}", limit(limits.max_buffer));
+    println!("String limit:      {}", limit(limits.max_string));
+    println!("Syscall limit:     {}", limit(limits.max_per_syscall));
+    for fault in &metadata.faults {
+        println!("Fault:             {fault}");
+    }
+    for delay in &metadata.delays {
+        println!("Delay:             {delay}");
+    }
+    for tamper in &metadata.tampers {
+        println!("Tamper:            {tamper}");
+    }
+    if let Some(len) = metadata.replay {
+        println!("Replayed:          trace of {len} items");
+    }
+    println!("Tracer command:    {}", metadata.tracer_args.join(" "));
+    match metadata.exit_status {
+        Some(status) => println!("Exit:              {}", ExitStatus::from_raw(status)),
+        None => println!("Exit:              the program didn't exit while it was traced"),
+    }
+}
+
+fn print_file_accesses(syscalls: Vec<TraceData>, mut symbolizer: Option<Symbolizer>) {
     let mut file_map = AHashMap::new();
     let mut files = Vec::new();
 
     for call in syscalls {
         match call {
-            TraceData::Event(trace_event) => {
-                if let TraceEvent::SyscallError {
-                    syscall,
-                    error,
-                    rip: _,
-                    cpu_time,
-                } = trace_event
-                {
-                    let cpu_time = CpuTimeFormat(cpu_time);
-                    println!("{cpu_time} Syscall {syscall} failed with {error}");
-                }
-            }
+            TraceData::Event(trace_event) => print_event(trace_event),
             TraceData::Syscall(syscall) => {
                 let cpu_time = CpuTimeFormat(syscall.cpu_time);
-                match syscall.syscall {
+                let duration = DurationFormat(syscall.time.duration());
+                let printed = match syscall.syscall {
                     SyscallInfo::Openat {
                         dirfd: _,
-                        mut pathname,
+                        pathname,
                         flags,
                         mode,
                         opened_fd,
                     } => {
-                        pathname.pop();
-                        let pathname = get_utf8(pathname);
-                        println!("{cpu_time} Opened file '{pathname}' with {flags:?} and {mode:?}");
+                        let pathname = get_utf8(pathname.without_nul().to_vec());
+                        println!(
+                            "{cpu_time} Opened file '{pathname}' with {flags} and {mode} {duration}"
+                        );
                         file_map.insert(
                             opened_fd,
                             FileData {
@@ -81,6 +282,7 @@ fn main() -> anyhow::Result<()> {
                                 total_requested_written_bytes: 0,
                             },
                         );
+                        true
                     }
                     SyscallInfo::Write {
                         fd,
@@ -89,13 +291,13 @@ fn main() -> anyhow::Result<()> {
                     } => {
                         if let Some(file_data) = file_map.get_mut(&fd) {
                             file_data.total_written_bytes += written_count;
-                            file_data.total_requested_written_bytes += to_write.len();
+                            file_data.total_requested_written_bytes += to_write.original_len;
                             println!(
-                                "{cpu_time} Wrote {} bytes into file {} ({written_count} bytes requested)",
-                                to_write.len(),
-                                file_data.pathname
+                                "{cpu_time} Wrote {} bytes into file {} ({written_count} bytes requested) {duration}",
+                                to_write.original_len, file_data.pathname
                             );
                         }
+                        file_map.contains_key(&fd)
                     }
                     SyscallInfo::Read {
                         fd,
@@ -103,28 +305,117 @@ fn main() -> anyhow::Result<()> {
                         requested_count,
                     } => {
                         if let Some(file_data) = file_map.get_mut(&fd) {
-                            file_data.total_read_bytes += read_bytes.len();
+                            file_data.total_read_bytes += read_bytes.original_len;
                             file_data.total_requested_read_bytes += requested_count;
                             println!(
-                                "{cpu_time} Read {} bytes from file {} ({requested_count} bytes requested)",
-                                read_bytes.len(),
-                                file_data.pathname
+                                "{cpu_time} Read {} bytes from file {} ({requested_count} bytes requested) {duration}",
+                                read_bytes.original_len, file_data.pathname
                             );
                         }
+                        file_map.contains_key(&fd)
                     }
                     SyscallInfo::Close { fd } => {
                         if let Some(file_data) = file_map.remove(&fd) {
                             files.push(file_data);
                         }
+                        false
+                    }
+                    _ => false,
+                };
+                if printed && let Some(symbolizer) = &mut symbolizer {
+                    print_frame(symbolizer, &syscall.call_site);
+                    for call_site in &syscall.stack {
+                        print_frame(symbolizer, call_site);
                     }
-                    _ => (),
                 }
             }
         }
     }
 
     files.extend(file_map.into_iter().map(|(_, value)| value));
+    print_opened_files(files);
+}
 
+fn print_event(event: TraceEvent) {
+    match event {
+        TraceEvent::SyscallError {
+            syscall,
+            error,
+            rip: _,
+            cpu_time,
+            time,
+        } => {
+            let cpu_time = CpuTimeFormat(cpu_time);
+            let duration = DurationFormat(time.duration());
+            println!("{cpu_time} Syscall {syscall} failed with {error} {duration}");
+        }
+        TraceEvent::Exec { executable } => {
+            println!("Executed '{}'", get_utf8(executable));
+        }
+        TraceEvent::FaultInjected { id, error, time } => {
+            let name = table::syscall_name(id).map_or(id.to_string(), str::to_owned);
+            let time = DurationFormat(time.duration());
+            println!("Injected {error} into syscall {name} {time}");
+        }
+        TraceEvent::DelayInjected { id, delay, at } => {
+            let name = table::syscall_name(id).map_or(id.to_string(), str::to_owned);
+            let at = match at {
+                DelayPoint::Entry => "entry",
+                DelayPoint::Exit => "exit",
+            };
+            let delay = DurationFormat(delay);
+            println!("Delayed syscall {name} at its {at} by {delay}");
+        }
+        TraceEvent::ReplayDiverged {
+            position,
+            expected,
+            actual,
+        } => {
+            let name = |id: u64| table::syscall_name(id).map_or(id.to_string(), str::to_owned);
+            let expected = expected.map_or("the end of the trace".to_owned(), name);
+            println!(
+                "Replay diverged at position {position}: expected {expected}, got {}",
+                name(actual)
+            );
+        }
+        TraceEvent::Tampered {
+            id,
+            original,
+            tampered,
+            written,
+        } => {
+            let name = table::syscall_name(id).map_or(id.to_string(), str::to_owned);
+            print!("Tampered syscall {name} to return {tampered} instead of {original}");
+            if written > 0 {
+                print!(", wrote {written} bytes into its buffer");
+            }
+            println!();
+        }
+        TraceEvent::SyscallSkipped {
+            id,
+            return_value,
+            time,
+        } => {
+            let name = table::syscall_name(id).map_or(id.to_string(), str::to_owned);
+            let time = DurationFormat(time.duration());
+            println!("Skipped syscall {name}, returned {return_value} {time}");
+        }
+        TraceEvent::Detached => println!("Detached from the process"),
+        _ => (),
+    }
+}
+
+fn print_frame(symbolizer: &mut Symbolizer, call_site: &CallSite) {
+    if let Some(frame) = symbolizer.symbolize(call_site) {
+        println!("            at {frame}");
+    } else if let Some(module) = &call_site.module {
+        println!("            at {module}+{:#x}", call_site.offset);
+    } else {
+        println!("            at {:#x}", call_site.address);
+    }
+}
+
+fn print_opened_files(files: Vec<FileData>) {
     println!("The process has opened:");
     for value in files {
         println!("{}", value.pathname);
@@ -141,8 +432,6 @@ fn main() -> anyhow::Result<()> {
             );
         }
     }
-    // println!("parsed data: {syscalls:?}");
-    Ok(())
 }
 
 fn get_utf8(bytes: Vec<u8>) -> String {
@@ -1,2 +1,8 @@
+pub mod redact;
+pub mod seccomp;
+pub mod summary;
+pub mod symbolize;
 pub mod syscall;
+pub mod trace_file;
 pub mod tracee;
+pub mod tracer;
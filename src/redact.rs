@@ -0,0 +1,407 @@
+//! Removal of secrets from captured syscall data
+//!
+//! Environment variables, buffers of reads and writes and paths can contain API tokens or
+//! passwords. A [`Redactor`] replaces them by `<redacted>`, or by `<hmac-sha256:...>` of the
+//! replaced bytes in [`RedactMode::Hash`], so equal secrets can still be matched across the trace.
+//! The hashes are keyed by a random key of each redactor, unless a key is given with
+//! [`Redactor::hash_key`], so short secrets can't be found by hashing guesses. Hashes of truncated
+//! data only cover the captured part.
+use std::{collections::HashSet, fmt::Write, str};
+#[cfg(feature = "binary")]
+use std::{os::unix::ffi::OsStringExt, path::PathBuf};
+
+use regex::bytes::{Captures, Regex};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    syscall::{SyscallInfo, TraceData, capture::CapturedBytes, table::ArgValue},
+    trace_file::TraceMetadata,
+};
+
+/// What is stored in place of redacted data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RedactMode {
+    /// Replace the data by `<redacted>`
+    #[default]
+    Remove,
+    /// Replace the data by `<hmac-sha256:HEX>`, an HMAC-SHA256 of the data keyed by the key of
+    /// the redactor
+    Hash,
+}
+
+/// Set of redaction rules applied to syscalls, either while tracing (see
+/// [`SyscallIterOpts::redact`](crate::syscall::SyscallIterOpts::redact)) or to a saved trace
+///
+/// It keeps track of file descriptors opened from dropped paths, so syscalls have to be passed
+/// to it in the order they were called.
+#[derive(Debug, Default, Clone)]
+pub struct Redactor {
+    mode: RedactMode,
+    hash_key: Vec<u8>,
+    env_patterns: Vec<String>,
+    buffer_patterns: Vec<Regex>,
+    fds: HashSet<i32>,
+    paths: Vec<Vec<u8>>,
+    /// Currently open file descriptors of files from `paths`
+    path_fds: HashSet<i32>,
+}
+
+impl Redactor {
+    /// Creates a redactor without any rules, hashes of [`RedactMode::Hash`] are keyed by a new
+    /// random key
+    #[must_use]
+    pub fn new(mode: RedactMode) -> Self {
+        Self {
+            mode,
+            hash_key: random_key().to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// Key hashes of [`RedactMode::Hash`] by `key`, so they can be compared across traces
+    /// redacted with the same key
+    #[must_use]
+    pub fn hash_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.hash_key = key.into();
+        self
+    }
+
+    /// Redact values of environment variables with names matching a glob `pattern`, like
+    /// `*_TOKEN` or `AWS_*`
+    #[must_use]
+    pub fn env_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.env_patterns.push(pattern.into());
+        self
+    }
+
+    /// Redact all matches of a regular expression in captured buffers and strings
+    pub fn buffer_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.buffer_patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Redact whole payloads of reads and writes on `fd`
+    #[must_use]
+    pub fn drop_fd(mut self, fd: i32) -> Self {
+        self.fds.insert(fd);
+        self
+    }
+
+    /// Redact whole payloads of reads and writes on files opened from `path` or from a directory
+    /// `path`, it's compared to the path passed to `openat` as is
+    #[must_use]
+    pub fn drop_path(mut self, path: impl Into<Vec<u8>>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    pub fn redact_data(&mut self, data: &mut TraceData) {
+        if let TraceData::Syscall(syscall) = data {
+            self.redact(&mut syscall.syscall);
+        }
+    }
+
+    pub fn redact(&mut self, syscall: &mut SyscallInfo) {
+        match syscall {
+            SyscallInfo::Read {
+                fd,
+                read_bytes: data,
+                ..
+            }
+            | SyscallInfo::Write {
+                fd, to_write: data, ..
+            }
+            | SyscallInfo::Recvfrom {
+                sockfd: fd,
+                received_bytes: data,
+                ..
+            } => {
+                if self.fds.contains(fd) || self.path_fds.contains(fd) {
+                    self.replace_all(data);
+                } else {
+                    self.redact_buffer(data);
+                }
+            }
+            SyscallInfo::Openat {
+                pathname,
+                opened_fd,
+                ..
+            } => {
+                let path = pathname.without_nul();
+                if self.paths.iter().any(|dropped| path_matches(dropped, path)) {
+                    self.path_fds.insert(*opened_fd);
+                } else {
+                    // the fd could have been reused without us seeing its close
+                    self.path_fds.remove(opened_fd);
+                }
+                self.redact_buffer(pathname);
+            }
+            SyscallInfo::Close { fd } => {
+                self.path_fds.remove(fd);
+            }
+            SyscallInfo::Execve {
+                pathname,
+                argv,
+                envp,
+            }
+            | SyscallInfo::Execveat {
+                pathname,
+                argv,
+                envp,
+                ..
+            } => {
+                self.redact_buffer(pathname);
+                for arg in argv {
+                    self.redact_buffer(arg);
+                }
+                for entry in envp {
+                    self.redact_env(entry);
+                    self.redact_buffer(entry);
+                }
+            }
+            SyscallInfo::Unlink { pathname } => self.redact_buffer(pathname),
+            SyscallInfo::Unknown { decoded_args, .. } => {
+                for arg in decoded_args {
+                    if let ArgValue::Path(data) | ArgValue::String(data) = arg {
+                        self.redact_buffer(data);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Applies buffer patterns to the command lines, paths, environment and rules in `metadata`
+    ///
+    /// Values of the environment variables are redacted like those passed to `execve`.
+    pub fn redact_metadata(&self, metadata: &mut TraceMetadata) {
+        for path in [&mut metadata.executable, &mut metadata.work_dir] {
+            if let Some(replaced) = self.replace_patterns(path) {
+                *path = replaced;
+            }
+        }
+        for arg in &mut metadata.args {
+            if let Some(replaced) = self.replace_patterns(arg) {
+                *arg = replaced;
+            }
+        }
+        for entry in &mut metadata.env {
+            if let Some(replaced) = self.replace_env_value(entry) {
+                *entry = replaced;
+            }
+            if let Some(replaced) = self.replace_patterns(entry) {
+                *entry = replaced;
+            }
+        }
+        let strings = [
+            &mut metadata.tracer_args,
+            &mut metadata.faults,
+            &mut metadata.delays,
+            &mut metadata.tampers,
+        ];
+        for string in strings.into_iter().flatten() {
+            if let Some(replaced) = self.replace_patterns(string.as_bytes()) {
+                *string = String::from_utf8_lossy(&replaced).into_owned();
+            }
+        }
+    }
+
+    fn marker(&self, secret: &[u8]) -> Vec<u8> {
+        match self.mode {
+            RedactMode::Remove => b"<redacted>".to_vec(),
+            RedactMode::Hash => {
+                let mut marker = "<hmac-sha256:".to_owned();
+                for byte in hmac_sha256(&self.hash_key, secret) {
+                    let _ = write!(marker, "{byte:02x}");
+                }
+                marker.push('>');
+                marker.into_bytes()
+            }
+        }
+    }
+
+    fn replace_all(&self, data: &mut CapturedBytes) {
+        data.data = self.marker(&data.data);
+        data.redacted = true;
+    }
+
+    fn redact_buffer(&self, data: &mut CapturedBytes) {
+        if let Some(replaced) = self.replace_patterns(&data.data) {
+            data.data = replaced;
+            data.redacted = true;
+        }
+    }
+
+    /// Replaces matches of buffer patterns in `data`, `None` if nothing matched
+    fn replace_patterns(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let mut result: Option<Vec<u8>> = None;
+        for pattern in &self.buffer_patterns {
+            let current = result.as_deref().unwrap_or(data);
+            let replaced = pattern
+                .replace_all(current, |captures: &Captures| self.marker(&captures[0]))
+                .into_owned();
+            if replaced != current {
+                result = Some(replaced);
+            }
+        }
+        result
+    }
+
+    /// Redacts value of a single `NAME=value` environment variable
+    fn redact_env(&self, entry: &mut CapturedBytes) {
+        let len = entry.without_nul().len();
+        if let Some(replaced) = self.replace_env_value(&entry.data[..len]) {
+            entry.data.splice(..len, replaced);
+            entry.redacted = true;
+        }
+    }
+
+    /// `entry` with its value replaced, `None` if its name doesn't match an env pattern
+    fn replace_env_value(&self, entry: &[u8]) -> Option<Vec<u8>> {
+        let equals = entry.iter().position(|byte| *byte == b'=')?;
+        let name = str::from_utf8(&entry[..equals]).ok()?;
+        if !self
+            .env_patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern.as_bytes(), name.as_bytes()))
+        {
+            return None;
+        }
+        let mut replaced = entry[..=equals].to_vec();
+        replaced.extend(self.marker(&entry[equals + 1..]));
+        Some(replaced)
+    }
+}
+
+/// Command line options of redaction, shared by the tracer and the viewer
+#[cfg(feature = "binary")]
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct RedactArgs {
+    /// Redact values of environment variables with names matching a glob <PATTERN>, e.g. '*_TOKEN'
+    #[arg(long, value_name = "PATTERN")]
+    pub redact_env: Vec<String>,
+    /// Redact all matches of a regular expression <REGEX> in captured buffers and strings
+    #[arg(long, value_name = "REGEX")]
+    pub redact_regex: Vec<String>,
+    /// Redact whole payloads of reads and writes on <FD>
+    #[arg(long, value_name = "FD")]
+    pub redact_fd: Vec<i32>,
+    /// Redact whole payloads of reads and writes on files opened from <PATH>
+    #[arg(long, value_name = "PATH")]
+    pub redact_path: Vec<PathBuf>,
+    /// Replace redacted data by their HMAC-SHA256 hashes instead of removing them
+    ///
+    /// The hashes are keyed by a random key, so they can only be compared within one trace,
+    /// unless --redact-key is given.
+    #[arg(long)]
+    pub redact_hash: bool,
+    /// Key the hashes of --redact-hash by <KEY>, so they can be compared across traces
+    #[arg(long, value_name = "KEY", requires = "redact_hash")]
+    pub redact_key: Option<String>,
+}
+
+#[cfg(feature = "binary")]
+impl RedactArgs {
+    /// Returns `true` if no data would be redacted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.redact_env.is_empty()
+            && self.redact_regex.is_empty()
+            && self.redact_fd.is_empty()
+            && self.redact_path.is_empty()
+    }
+
+    pub fn redactor(&self) -> Result<Redactor, regex::Error> {
+        let mode = if self.redact_hash {
+            RedactMode::Hash
+        } else {
+            RedactMode::Remove
+        };
+        let mut redactor = Redactor::new(mode);
+        if let Some(key) = &self.redact_key {
+            redactor = redactor.hash_key(key.as_bytes());
+        }
+        for pattern in &self.redact_env {
+            redactor = redactor.env_pattern(pattern);
+        }
+        for pattern in &self.redact_regex {
+            redactor = redactor.buffer_pattern(pattern)?;
+        }
+        for fd in &self.redact_fd {
+            redactor = redactor.drop_fd(*fd);
+        }
+        for path in &self.redact_path {
+            redactor = redactor.drop_path(path.clone().into_os_string().into_vec());
+        }
+        Ok(redactor)
+    }
+}
+
+/// HMAC-SHA256 of `data` keyed by `key`, as defined by RFC 2104
+fn hmac_sha256(key: &[u8], data: &[u8]) -> impl IntoIterator<Item = u8> {
+    const BLOCK_LEN: usize = 64;
+    let mut block = [0; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        let digest = Sha256::digest(key);
+        block[..digest.len()].copy_from_slice(&digest);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+}
+
+fn random_key() -> [u8; 32] {
+    let mut key = [0; 32];
+    let read = unsafe { libc::getrandom(key.as_mut_ptr().cast(), key.len(), 0) };
+    // reads of up to 256 bytes are not interrupted and don't fail once the kernel is seeded
+    assert_eq!(
+        read,
+        key.len() as isize,
+        "could not generate a redaction key: {}",
+        std::io::Error::last_os_error()
+    );
+    key
+}
+
+/// Matches `text` against a glob `pattern` with `*` and `?` wildcards
+#[must_use]
+pub fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` and the text position it's currently matching up to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(byte) if *byte == b'?' || *byte == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|byte| *byte == b'*')
+}
+
+fn path_matches(dropped: &[u8], path: &[u8]) -> bool {
+    match path.strip_prefix(dropped) {
+        Some(rest) => rest.is_empty() || dropped.ends_with(b"/") || rest.starts_with(b"/"),
+        None => false,
+    }
+}
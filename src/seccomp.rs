@@ -0,0 +1,147 @@
+//! Seccomp-BPF accelerated tracing
+//!
+//! Instead of stopping the tracee on entry and exit of every syscall, the tracee gets a seccomp
+//! filter returning `SECCOMP_RET_TRACE` only for the syscalls matched by a [`SyscallFilter`].
+//! The tracer then continues the tracee with `PTRACE_CONT` and only gets stopped on those
+//! syscalls, every other syscall runs at full speed.
+//!
+//! `execve` and `execveat` can't be stopped on by the filter, so [`SeccompProgram::new`] rejects
+//! filters matching them. The filter is installed before the tracee's own `execve`, which would
+//! fail with `ENOSYS` if it was traced, because the tracer cannot set `PTRACE_O_TRACESECCOMP`
+//! before that.
+use std::{
+    io, mem,
+    os::unix::process::CommandExt,
+    process::{Child, Command},
+};
+
+use libc::{
+    BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, SECCOMP_RET_ALLOW, SECCOMP_RET_TRACE,
+    seccomp_data, sock_filter, sock_fprog,
+};
+use nix::{
+    errno::Errno,
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{WaitStatus, waitpid},
+    },
+    unistd::Pid,
+};
+
+use thiserror::Error;
+
+use crate::syscall::filter::SyscallFilter;
+
+/// `AUDIT_ARCH_X86_64` from `linux/audit.h`, which is missing in libc
+const AUDIT_ARCH_X86_64: u32 = 0xC000_003E;
+
+/// Syscalls which the seccomp filter can't stop on
+const UNTRACEABLE: [(i64, &str); 2] = [
+    (libc::SYS_execve, "execve"),
+    (libc::SYS_execveat, "execveat"),
+];
+
+/// Syscall matched by a [`SyscallFilter`] which can't be traced with seccomp
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("{0} can't be traced with seccomp, it has to be excluded from the filter")]
+pub struct UntraceableSyscall(pub &'static str);
+
+/// Compiled seccomp filter which makes the kernel stop the tracee on syscalls matched by a
+/// [`SyscallFilter`]
+#[derive(Debug, Clone)]
+pub struct SeccompProgram(Vec<sock_filter>);
+
+impl SeccompProgram {
+    /// Compiles `filter`, which must not match `execve` and `execveat`
+    pub fn new(filter: &SyscallFilter) -> Result<Self, UntraceableSyscall> {
+        if let Some((_, name)) = UNTRACEABLE
+            .iter()
+            .find(|(number, _)| filter.matches(number.cast_unsigned()))
+        {
+            return Err(UntraceableSyscall(name));
+        }
+        let mut program = vec![
+            // syscalls of other ABIs are not traced
+            stmt(
+                BPF_LD | BPF_W | BPF_ABS,
+                mem::offset_of!(seccomp_data, arch) as u32,
+            ),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH_X86_64, 1, 0),
+            stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+            stmt(
+                BPF_LD | BPF_W | BPF_ABS,
+                mem::offset_of!(seccomp_data, nr) as u32,
+            ),
+        ];
+        // every comparison has its own return, so jump offsets never overflow u8
+        for number in filter.syscall_numbers() {
+            program.push(jump(BPF_JMP | BPF_JEQ | BPF_K, number as u32, 0, 1));
+            program.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_TRACE));
+        }
+        program.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+        Ok(Self(program))
+    }
+
+    /// Installs the filter to the calling process
+    ///
+    /// This sets `PR_SET_NO_NEW_PRIVS`, so set-user-ID binaries will not gain privileges.
+    pub fn install(&self) -> Result<(), Errno> {
+        let prog = sock_fprog {
+            len: self.0.len() as u16,
+            filter: self.0.as_ptr().cast_mut(),
+        };
+        unsafe {
+            Errno::result(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+            Errno::result(libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                0,
+                &raw const prog,
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// Spawns `cmd` as a tracee with a seccomp filter for syscalls matched by `filter`
+///
+/// The child is stopped after its `execve`, the same way as with
+/// [`spawn_ptrace`](spawn_ptrace::CommandPtraceSpawn::spawn_ptrace). Tracing of the child needs
+/// to be done with [`SyscallIterOpts::seccomp`](crate::syscall::SyscallIterOpts::seccomp)
+/// enabled. A filter matching `execve` or `execveat` fails with [`io::ErrorKind::InvalidInput`]
+/// before anything is spawned.
+pub fn spawn_ptrace(cmd: &mut Command, filter: &SyscallFilter) -> io::Result<Child> {
+    let program = SeccompProgram::new(filter)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let child = unsafe {
+        cmd.pre_exec(move || {
+            ptrace::traceme()?;
+            program.install()?;
+            Ok(())
+        })
+        .spawn()?
+    };
+    match waitpid(Pid::from_raw(child.id().cast_signed()), None)? {
+        WaitStatus::Stopped(_, Signal::SIGTRAP) => (),
+        status => {
+            return Err(io::Error::other(format!(
+                "unexpected child state {status:?}"
+            )));
+        }
+    }
+    Ok(child)
+}
+
+fn stmt(code: u32, k: u32) -> sock_filter {
+    jump(code, k, 0, 0)
+}
+
+fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
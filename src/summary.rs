@@ -0,0 +1,253 @@
+use std::{borrow::Borrow, collections::HashMap, fmt::Display, time::Duration};
+
+use nix::errno::Errno;
+
+use crate::syscall::{
+    SyscallInfo, SyscallInfoDiscriminants, TraceData, parse_error::TraceEvent, table,
+};
+
+/// Key under which syscalls are aggregated, decoded syscalls use their discriminant and unknown
+/// syscalls their raw number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SummaryKey {
+    Known(SyscallInfoDiscriminants),
+    Unknown(u64),
+}
+
+impl Display for SummaryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SummaryKey::Known(syscall) => write!(f, "{syscall}"),
+            SummaryKey::Unknown(id) => match table::syscall_name(*id) {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "syscall_{id}"),
+            },
+        }
+    }
+}
+
+impl SummaryKey {
+    /// Key of the syscall `number`, the same as of its decoded [`SyscallInfo`] if there is one
+    #[must_use]
+    pub fn from_number(number: u64) -> Self {
+        table::syscall_name(number)
+            .and_then(|name| name.parse().ok())
+            .map_or(SummaryKey::Unknown(number), SummaryKey::Known)
+    }
+}
+
+impl From<&SyscallInfo> for SummaryKey {
+    fn from(value: &SyscallInfo) -> Self {
+        match value {
+            SyscallInfo::Unknown { id, .. } => SummaryKey::Unknown(*id),
+            syscall => SummaryKey::Known(syscall.into()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SyscallStats {
+    pub calls: usize,
+    pub errors: usize,
+    pub total_time: Duration,
+    /// Number of failed calls for each returned errno, in order of first appearance
+    pub errnos: Vec<(Errno, usize)>,
+}
+
+impl SyscallStats {
+    #[must_use]
+    pub fn average_time(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        let average = self.total_time.as_nanos() / self.calls as u128;
+        Duration::from_nanos(u64::try_from(average).unwrap_or(u64::MAX))
+    }
+
+    fn add_error(&mut self, error: Errno) {
+        self.errors += 1;
+        match self.errnos.iter_mut().find(|(errno, _)| *errno == error) {
+            Some((_, count)) => *count += 1,
+            None => self.errnos.push((error, 1)),
+        }
+    }
+}
+
+/// Per-syscall statistics (count, errors and time) over a stream of [`TraceData`], similar to
+/// `strace -c`
+#[derive(Debug, Default, Clone)]
+pub struct TraceSummary {
+    stats: HashMap<SummaryKey, SyscallStats>,
+}
+
+impl TraceSummary {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a syscall to the statistics, other events are ignored
+    ///
+    /// Syscalls failed by [`TraceEvent::FaultInjected`] and skipped by
+    /// [`TraceEvent::SyscallSkipped`] are counted too, with the result the tracee saw. Undecoded
+    /// syscalls failed if they returned `-4095..0`.
+    pub fn add(&mut self, data: &TraceData) {
+        match data {
+            TraceData::Syscall(syscall) => {
+                let stats = self.stats.entry((&syscall.syscall).into()).or_default();
+                stats.calls += 1;
+                stats.total_time += syscall.time.duration();
+                if let SyscallInfo::Unknown { return_value, .. } = syscall.syscall
+                    && let Some(error) = return_error(return_value)
+                {
+                    stats.add_error(error);
+                }
+            }
+            TraceData::Event(TraceEvent::SyscallError {
+                syscall,
+                error,
+                time,
+                ..
+            }) => {
+                let stats = self.stats.entry(SummaryKey::Known(*syscall)).or_default();
+                stats.calls += 1;
+                stats.total_time += time.duration();
+                stats.add_error(*error);
+            }
+            TraceData::Event(TraceEvent::FaultInjected { id, error, time }) => {
+                let stats = self.stats.entry(SummaryKey::from_number(*id)).or_default();
+                stats.calls += 1;
+                stats.total_time += time.duration();
+                stats.add_error(*error);
+            }
+            TraceData::Event(TraceEvent::SyscallSkipped {
+                id,
+                return_value,
+                time,
+            }) => {
+                let stats = self.stats.entry(SummaryKey::from_number(*id)).or_default();
+                stats.calls += 1;
+                stats.total_time += time.duration();
+                if let Some(error) = return_error(*return_value) {
+                    stats.add_error(error);
+                }
+            }
+            TraceData::Event(_) => (),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, key: SummaryKey) -> Option<&SyscallStats> {
+        self.stats.get(&key)
+    }
+
+    /// Statistics sorted by total time spent in the syscall, then by number of calls
+    #[must_use]
+    pub fn sorted(&self) -> Vec<(SummaryKey, &SyscallStats)> {
+        let mut sorted: Vec<_> = self
+            .stats
+            .iter()
+            .map(|(key, stats)| (*key, stats))
+            .collect();
+        sorted.sort_by(|(key_a, a), (key_b, b)| {
+            b.total_time
+                .cmp(&a.total_time)
+                .then(b.calls.cmp(&a.calls))
+                .then(key_a.cmp(key_b))
+        });
+        sorted
+    }
+
+    #[must_use]
+    pub fn total(&self) -> SyscallStats {
+        let mut total = SyscallStats::default();
+        for stats in self.stats.values() {
+            total.calls += stats.calls;
+            total.errors += stats.errors;
+            total.total_time += stats.total_time;
+        }
+        total
+    }
+}
+
+impl<T: Borrow<TraceData>> Extend<T> for TraceSummary {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for data in iter {
+            self.add(data.borrow());
+        }
+    }
+}
+
+impl<T: Borrow<TraceData>> FromIterator<T> for TraceSummary {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut summary = Self::new();
+        summary.extend(iter);
+        summary
+    }
+}
+
+const SEPARATOR: &str = "------ ----------- ----------- --------- --------- ----------------";
+
+impl Display for TraceSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let total = self.total();
+        let total_secs = total.total_time.as_secs_f64();
+        let sorted = self.sorted();
+
+        writeln!(
+            f,
+            "% time     seconds  usecs/call     calls    errors syscall"
+        )?;
+        writeln!(f, "{SEPARATOR}")?;
+        for (key, stats) in &sorted {
+            let percent = if total_secs > 0.0 {
+                stats.total_time.as_secs_f64() / total_secs * 100.0
+            } else {
+                0.0
+            };
+            write!(
+                f,
+                "{percent:>6.2} {:>11.6} {:>11} {:>9} ",
+                stats.total_time.as_secs_f64(),
+                stats.average_time().as_micros(),
+                stats.calls,
+            )?;
+            if stats.errors > 0 {
+                write!(f, "{:>9}", stats.errors)?;
+            } else {
+                write!(f, "{:>9}", "")?;
+            }
+            writeln!(f, " {key}")?;
+        }
+        writeln!(f, "{SEPARATOR}")?;
+        let percent = if total_secs > 0.0 { 100.0 } else { 0.0 };
+        writeln!(
+            f,
+            "{percent:>6.2} {total_secs:>11.6} {:>11} {:>9} {:>9} total",
+            total.average_time().as_micros(),
+            total.calls,
+            total.errors,
+        )?;
+
+        if total.errors > 0 {
+            writeln!(f)?;
+            writeln!(f, "errors:")?;
+            for (key, stats) in sorted.iter().filter(|(_, stats)| stats.errors > 0) {
+                let errnos: Vec<_> = stats
+                    .errnos
+                    .iter()
+                    .map(|(errno, count)| format!("{errno:?} x{count}"))
+                    .collect();
+                writeln!(f, "  {key}: {}", errnos.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by a raw syscall, the kernel returns errors as `-4095..0`
+fn return_error(return_value: i64) -> Option<Errno> {
+    (-4095..0)
+        .contains(&return_value)
+        .then(|| Errno::from_raw(-return_value as i32))
+}
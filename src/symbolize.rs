@@ -0,0 +1,338 @@
+//! Resolving [`CallSite`]s to `function+offset (file:line)`
+//!
+//! Symbols are read from `.symtab` and `.dynsym` of the mapped modules. Source lines are read
+//! from DWARF line tables with the `dwarf` feature. Separate debug files are looked up by
+//! build-id in `/usr/lib/debug/.build-id/`. Modules are only used if their build-id matches the
+//! one recorded in the trace, so traces can be symbolized offline on a different machine.
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+};
+
+use elf::{ElfBytes, abi::PT_LOAD, abi::STT_FUNC, endian::AnyEndian};
+
+use crate::{syscall::CallSite, tracee::maps::read_build_id};
+
+/// Symbolized location of a [`CallSite`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: String,
+    /// Offset of the address from the start of `function`
+    pub offset: u64,
+    pub location: Option<SourceLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}+{:#x}", self.function, self.offset)?;
+        if let Some(location) = &self.location {
+            write!(f, " ({}:{})", location.file, location.line)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Symbol {
+    address: u64,
+    size: u64,
+    name: String,
+}
+
+/// Symbols and line table of a single module, addresses are virtual addresses of the ELF file
+#[derive(Debug, Default)]
+struct ModuleSymbols {
+    /// File offset, virtual address and size of loaded segments
+    segments: Vec<(u64, u64, u64)>,
+    /// Sorted by address
+    symbols: Vec<Symbol>,
+    #[cfg(feature = "dwarf")]
+    lines: dwarf::LineTable,
+}
+
+impl ModuleSymbols {
+    fn load(data: &[u8]) -> Option<Self> {
+        let elf = ElfBytes::<AnyEndian>::minimal_parse(data).ok()?;
+        let segments = elf
+            .segments()
+            .into_iter()
+            .flatten()
+            .filter(|phdr| phdr.p_type == PT_LOAD)
+            .map(|phdr| (phdr.p_offset, phdr.p_vaddr, phdr.p_filesz))
+            .collect();
+        let mut module = Self {
+            segments,
+            ..Self::default()
+        };
+        module.add_symbols(&elf);
+        #[cfg(feature = "dwarf")]
+        {
+            module.lines = dwarf::LineTable::load(&elf);
+        }
+        Some(module)
+    }
+
+    fn add_symbols(&mut self, elf: &ElfBytes<AnyEndian>) {
+        let tables = [elf.symbol_table(), elf.dynamic_symbol_table()];
+        for (symbols, strings) in tables.into_iter().filter_map(|table| table.ok().flatten()) {
+            for symbol in symbols.iter() {
+                if symbol.st_symtype() != STT_FUNC || symbol.st_value == 0 {
+                    continue;
+                }
+                let Ok(name) = strings.get(symbol.st_name as usize) else {
+                    continue;
+                };
+                self.symbols.push(Symbol {
+                    address: symbol.st_value,
+                    size: symbol.st_size,
+                    name: name.to_owned(),
+                });
+            }
+        }
+        self.symbols.sort_by_key(|symbol| symbol.address);
+        // .dynsym mostly duplicates .symtab
+        self.symbols
+            .dedup_by(|a, b| a.address == b.address && a.name == b.name);
+    }
+
+    fn virtual_address(&self, file_offset: u64) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|(offset, _, size)| (*offset..offset + size).contains(&file_offset))
+            .map(|(offset, address, _)| file_offset - offset + address)
+    }
+
+    /// Symbolizes the instruction before `file_offset`
+    ///
+    /// Call sites are return addresses or addresses right after the syscall instruction, the
+    /// instruction before them is the call or the syscall. A call at the very end of a function
+    /// would otherwise be attributed to the next function.
+    fn symbolize(&self, file_offset: u64) -> Option<Frame> {
+        let address = self.virtual_address(file_offset)?;
+        let instruction = address.checked_sub(1)?;
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= instruction)
+            .checked_sub(1)?;
+        let symbol = &self.symbols[index];
+        if symbol.size != 0 && instruction >= symbol.address + symbol.size {
+            return None;
+        }
+        #[cfg(feature = "dwarf")]
+        let location = self.lines.find(instruction);
+        #[cfg(not(feature = "dwarf"))]
+        let location = None;
+        Some(Frame {
+            function: symbol.name.clone(),
+            offset: address - symbol.address,
+            location,
+        })
+    }
+}
+
+/// Symbolizes [`CallSite`]s, symbols of every module are loaded only once
+#[derive(Debug)]
+pub struct Symbolizer {
+    debug_dirs: Vec<PathBuf>,
+    /// Keyed by module path and build-id, `None` if the module couldn't be loaded
+    modules: HashMap<(String, Option<Vec<u8>>), Option<ModuleSymbols>>,
+}
+
+impl Default for Symbolizer {
+    fn default() -> Self {
+        Self {
+            debug_dirs: vec![PathBuf::from("/usr/lib/debug")],
+            modules: HashMap::new(),
+        }
+    }
+}
+
+impl Symbolizer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also look for separate debug files in `<dir>/.build-id/`
+    #[must_use]
+    pub fn debug_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.debug_dirs.push(dir.into());
+        self
+    }
+
+    /// Returns `None` if the module or the symbol containing the call site isn't found
+    pub fn symbolize(&mut self, call_site: &CallSite) -> Option<Frame> {
+        let module = call_site.module.as_ref()?;
+        let key = (module.clone(), call_site.build_id.clone());
+        if !self.modules.contains_key(&key) {
+            let symbols = self.load_module(module, call_site.build_id.as_deref());
+            self.modules.insert(key.clone(), symbols);
+        }
+        self.modules[&key].as_ref()?.symbolize(call_site.offset)
+    }
+
+    fn load_module(&self, path: &str, build_id: Option<&[u8]>) -> Option<ModuleSymbols> {
+        let path = Path::new(path);
+        // the file could have been changed since it was traced
+        let module = if build_id.is_none() || read_build_id(path).as_deref() == build_id {
+            std::fs::read(path)
+                .ok()
+                .and_then(|data| ModuleSymbols::load(&data))
+        } else {
+            None
+        };
+        let debug_file = build_id.and_then(|build_id| self.debug_file(build_id));
+        let Some(debug_data) = debug_file.and_then(|debug_file| std::fs::read(debug_file).ok())
+        else {
+            return module;
+        };
+        let Some(mut debug) = ModuleSymbols::load(&debug_data) else {
+            return module;
+        };
+        if let Some(module) = module {
+            // the module itself is used for anything the debug file misses
+            if debug.symbols.is_empty() {
+                debug.symbols = module.symbols;
+            }
+            if debug.segments.is_empty() {
+                debug.segments = module.segments;
+            }
+        }
+        Some(debug)
+    }
+
+    fn debug_file(&self, build_id: &[u8]) -> Option<PathBuf> {
+        let (first, rest) = build_id.split_first()?;
+        let mut name = String::new();
+        for byte in rest {
+            let _ = write!(name, "{byte:02x}");
+        }
+        name.push_str(".debug");
+        self.debug_dirs
+            .iter()
+            .map(|dir| {
+                dir.join(".build-id")
+                    .join(format!("{first:02x}"))
+                    .join(&name)
+            })
+            .find(|path| path.is_file())
+    }
+}
+
+#[cfg(feature = "dwarf")]
+mod dwarf {
+    use std::collections::HashMap;
+
+    use elf::{ElfBytes, endian::AnyEndian};
+    use gimli::{Dwarf, EndianSlice, RunTimeEndian, SectionId};
+
+    use super::SourceLocation;
+
+    #[derive(Debug)]
+    struct LineRow {
+        address: u64,
+        file: usize,
+        line: u32,
+        /// First address after a sequence of instructions, it has no location
+        end_sequence: bool,
+    }
+
+    /// Rows of all DWARF line programs of a module, sorted by address
+    #[derive(Debug, Default)]
+    pub(super) struct LineTable {
+        files: Vec<String>,
+        rows: Vec<LineRow>,
+    }
+
+    impl LineTable {
+        pub(super) fn load(elf: &ElfBytes<'_, AnyEndian>) -> Self {
+            let mut table = Self::default();
+            let mut file_indices = HashMap::new();
+            let load = |id: SectionId| -> Result<_, gimli::Error> {
+                let data = elf
+                    .section_header_by_name(id.name())
+                    .ok()
+                    .flatten()
+                    .and_then(|header| elf.section_data(&header).ok())
+                    // compressed sections are not supported
+                    .and_then(|(data, compression)| compression.is_none().then_some(data))
+                    .unwrap_or_default();
+                Ok(EndianSlice::new(data, RunTimeEndian::Little))
+            };
+            let Ok(dwarf) = Dwarf::load(load) else {
+                return table;
+            };
+            let mut units = dwarf.units();
+            while let Ok(Some(header)) = units.next() {
+                let Ok(unit) = dwarf.unit(header) else {
+                    continue;
+                };
+                let Some(program) = unit.line_program.clone() else {
+                    continue;
+                };
+                let mut rows = program.rows();
+                while let Ok(Some((header, row))) = rows.next_row() {
+                    if row.end_sequence() {
+                        table.rows.push(LineRow {
+                            address: row.address(),
+                            file: 0,
+                            line: 0,
+                            end_sequence: true,
+                        });
+                        continue;
+                    }
+                    let Some(file) = row.file(header) else {
+                        continue;
+                    };
+                    let mut path = String::new();
+                    if let Some(directory) = file.directory(header)
+                        && let Ok(directory) = dwarf.attr_string(&unit, directory)
+                    {
+                        path.push_str(&directory.to_string_lossy());
+                        path.push('/');
+                    }
+                    if let Ok(name) = dwarf.attr_string(&unit, file.path_name()) {
+                        path.push_str(&name.to_string_lossy());
+                    }
+                    let file = *file_indices.entry(path).or_insert_with_key(|path| {
+                        table.files.push(path.clone());
+                        table.files.len() - 1
+                    });
+                    table.rows.push(LineRow {
+                        address: row.address(),
+                        file,
+                        line: row.line().map_or(0, |line| line.get() as u32),
+                        end_sequence: false,
+                    });
+                }
+            }
+            // a sequence can start at the address where another one ends
+            table
+                .rows
+                .sort_by_key(|row| (row.address, !row.end_sequence));
+            table
+        }
+
+        pub(super) fn find(&self, address: u64) -> Option<SourceLocation> {
+            let index = self
+                .rows
+                .partition_point(|row| row.address <= address)
+                .checked_sub(1)?;
+            let row = &self.rows[index];
+            if row.end_sequence {
+                return None;
+            }
+            Some(SourceLocation {
+                file: self.files[row.file].clone(),
+                line: row.line,
+            })
+        }
+    }
+}
@@ -1,17 +1,15 @@
 use core::str;
+use std::{collections::VecDeque, io, os::unix::ffi::OsStringExt, time::Duration};
 
 use elf::{ElfBytes, endian::AnyEndian};
 use libc::{sockaddr, socklen_t};
 use log::{debug, trace, warn};
-use new_types::{NewTypeSer, SocketType, sockaddr_ser};
+pub(crate) use new_types::{NewTypeSer, sockaddr_ser};
 use nix::{
     errno::Errno,
-    fcntl::{self, OFlag},
     sys::{
         ptrace::Options,
-        signal::Signal,
         socket::{self, AddressFamily},
-        stat::{self, Mode},
     },
 };
 pub use parse_error::TraceError;
@@ -19,15 +17,35 @@ use rkyv::with;
 use thiserror::Error;
 
 use crate::{
-    syscall::parse_error::{TraceErrEvt, TraceEvent},
-    tracee::{PtraceSyscallInfo, PtraceSyscallInfoData, Tracee, WaitEvents, parse_syscall_error},
+    redact::Redactor,
+    syscall::{
+        capture::{CaptureLimits, CapturedBytes},
+        filter::SyscallFilter,
+        flags::{AtFlags, CloneFlags, FileMode, OpenFlags, RandomFlags, RecvFlags, SocketType},
+        handler::{Action, SyscallEntry, TraceHandler},
+        inject::{Delay, Fault, Injector, Tamper},
+        parse_error::{TraceErrEvt, TraceEvent},
+        replay::Replay,
+        table::ArgValue,
+    },
+    tracee::{
+        PtraceSyscallInfo, PtraceSyscallInfoData, Tracee, WaitEvents, maps, window::TraceWindow,
+    },
 };
 
 mod new_types;
 pub use new_types::SyscallNewTypeError;
 
+pub mod capture;
+pub mod filter;
+pub mod flags;
+pub mod handler;
+pub mod inject;
 pub mod parse_error;
-mod sock_type;
+pub mod replay;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod table;
 
 // FIXME many syscalls don't store their return values
 #[derive(
@@ -42,28 +60,42 @@ mod sock_type;
     Eq,
 )]
 #[rkyv(derive(Debug))]
-#[strum_discriminants(derive(strum::Display, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[strum_discriminants(derive(
+    strum::Display,
+    strum::EnumString,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    Hash,
+    PartialOrd,
+    Ord
+))]
+// same names as the kernel uses, e.g. `exit_group`
+#[strum_discriminants(strum(serialize_all = "snake_case"))]
+// tags are stored in trace files, new variants take the next unused one and bump the format
+// version, see `trace_file`
+#[repr(u8)]
 pub enum SyscallInfo {
     Read {
         fd: i32,
-        read_bytes: Vec<u8>,
+        read_bytes: CapturedBytes,
         requested_count: usize,
-    },
+    } = 0,
     Write {
         fd: i32,
-        to_write: Vec<u8>,
+        to_write: CapturedBytes,
         written_count: usize,
-    },
+    } = 1,
     Close {
         fd: i32,
-    },
+    } = 2,
     Socket {
         #[rkyv(with = NewTypeSer)]
         domain: AddressFamily,
         r#type: SocketType,
         // TODO this could be SockProtocol from nix, if it was easy to parse
         protocol: i32,
-    },
+    } = 3,
     Bind {
         sockfd: i32,
         // TODO the representation of all sockaddr could be a bit more readable by parsing it
@@ -71,59 +103,103 @@ pub enum SyscallInfo {
         #[rkyv(with = with::Map<sockaddr_ser>)]
         addr: Option<sockaddr>,
         addrlen: socklen_t,
-    },
+    } = 4,
     Listen {
         sockfd: i32,
         backlog: i32,
-    },
+    } = 5,
     Accept {
         sockfd: i32,
         // #[rkyv(with = ArchivedOption<Archivedsockaddr>)]
         #[rkyv(with = with::Map<sockaddr_ser>)]
         addr: Option<sockaddr>,
         addrlen: Option<socklen_t>,
-    },
+    } = 6,
     Openat {
         dirfd: i32,
-        pathname: Vec<u8>,
-        #[rkyv(with = NewTypeSer)]
-        flags: OFlag,
-        #[rkyv(with = NewTypeSer)]
-        mode: Mode,
+        pathname: CapturedBytes,
+        flags: OpenFlags,
+        mode: FileMode,
         opened_fd: i32,
-    },
+    } = 7,
     Execve {
-        pathname: Vec<u8>,
-        argv: Vec<u8>,
-        envp: Vec<u8>,
-    },
+        pathname: CapturedBytes,
+        argv: Vec<CapturedBytes>,
+        envp: Vec<CapturedBytes>,
+    } = 8,
+    Execveat {
+        dirfd: i32,
+        pathname: CapturedBytes,
+        argv: Vec<CapturedBytes>,
+        envp: Vec<CapturedBytes>,
+        /// `AT_EMPTY_PATH` and `AT_SYMLINK_NOFOLLOW`
+        flags: AtFlags,
+    } = 9,
     // this is ONLY FOR x86-64
     Clone {
-        // TODO eventually use nix::sched::CloneFlags, currently they don't support all the flags
-        flags: u64,
+        flags: CloneFlags,
         stack: usize,
         /// Pointer to i32 in child
         parent_tid: usize,
         /// Pointer to i32 in child
         child_tid: usize,
         tls: u64,
-    },
+    } = 10,
     ExitGroup {
         status: i32,
-    },
+    } = 11,
     Unlink {
-        pathname: Vec<u8>,
-    },
+        pathname: CapturedBytes,
+    } = 12,
+    Recvfrom {
+        sockfd: i32,
+        received_bytes: CapturedBytes,
+        requested_count: usize,
+        flags: RecvFlags,
+    } = 13,
+    Getrandom {
+        random_bytes: CapturedBytes,
+        requested_count: usize,
+        flags: RandomFlags,
+    } = 14,
+    ClockGettime {
+        clock_id: i32,
+        /// Time written by the kernel, `None` if no buffer was passed
+        time: Option<Duration>,
+    } = 15,
     Unknown {
         id: u64,
         args: [u64; 6],
+        /// Best-effort decoding of `args` by the syscall's signature, empty for syscalls that
+        /// aren't in [`table::SYSCALLS`]
+        decoded_args: Vec<ArgValue>,
         return_value: i64,
-    },
+    } = 16,
 }
 
 type SyscallDisc = SyscallInfoDiscriminants;
 
 impl SyscallInfo {
+    /// Name of the syscall as used by the kernel, if known
+    #[must_use]
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            SyscallInfo::Unknown { id, .. } => table::syscall_name(*id),
+            syscall => table::lookup_name(&SyscallDisc::from(syscall).to_string())
+                .map(|signature| signature.name),
+        }
+    }
+
+    /// Number of the syscall, if known
+    #[must_use]
+    pub fn number(&self) -> Option<u64> {
+        match self {
+            SyscallInfo::Unknown { id, .. } => Some(*id),
+            syscall => table::lookup_name(&SyscallDisc::from(syscall).to_string())
+                .map(|signature| signature.number),
+        }
+    }
+
     #[expect(
         clippy::too_many_lines,
         reason = "I will not be able to make this shorter"
@@ -145,15 +221,19 @@ impl SyscallInfo {
         match syscall_number.cast_signed() {
             libc::SYS_read => {
                 let read = tracee.parse_return(SyscallDisc::Read)?;
-                let bytes = tracee.memcpy(args[1], read as usize)?;
+                let bytes = tracee
+                    .capture(args[1], read as usize)
+                    .unwrap_or_else(|_| CapturedBytes::unreadable(read as usize));
                 Ok(SyscallInfo::Read {
                     fd: args[0] as libc::c_int,
                     read_bytes: bytes,
-                    requested_count: args[2].try_into().unwrap(),
+                    requested_count: args[2] as usize,
                 })
             }
             libc::SYS_write => {
-                let text = tracee.memcpy(args[1], args[2] as usize)?;
+                let text = tracee
+                    .capture(args[1], args[2] as usize)
+                    .unwrap_or_else(|_| CapturedBytes::unreadable(args[2] as usize));
                 let written_count = tracee.parse_return(SyscallDisc::Write)? as usize;
                 Ok(SyscallInfo::Write {
                     fd: args[0] as libc::c_int,
@@ -170,8 +250,13 @@ impl SyscallInfo {
             libc::SYS_socket => {
                 tracee.parse_return(SyscallDisc::Socket)?;
                 Ok(SyscallInfo::Socket {
-                    domain: socket::AddressFamily::from_i32(args[0] as libc::c_int).unwrap(),
-                    r#type: SocketType::try_from(args[1] as libc::c_int)?,
+                    domain: socket::AddressFamily::from_i32(args[0] as libc::c_int).ok_or(
+                        TraceError::InvalidArgument {
+                            syscall: SyscallDisc::Socket,
+                            value: args[0],
+                        },
+                    )?,
+                    r#type: SocketType(args[1] as libc::c_int),
                     protocol: args[2] as libc::c_int,
                 })
             }
@@ -179,11 +264,16 @@ impl SyscallInfo {
                 let addrlen = if args[1] == 0 || args[2] == 0 {
                     None
                 } else {
-                    let bytes = tracee.memcpy(args[2], std::mem::size_of::<socklen_t>())?;
-                    Some(socklen_t::from_ne_bytes(bytes.try_into().unwrap()))
+                    tracee
+                        .memcpy(args[2], std::mem::size_of::<socklen_t>())
+                        .ok()
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .map(socklen_t::from_ne_bytes)
                 };
                 tracee.parse_return(SyscallDisc::Accept)?;
-                let sock_addr = tracee.memcpy_struct::<sockaddr>(args[1])?;
+                let sock_addr = tracee
+                    .memcpy_struct::<sockaddr>(args[1])
+                    .unwrap_or_default();
                 Ok(SyscallInfo::Accept {
                     sockfd: args[0] as libc::c_int,
                     addr: sock_addr,
@@ -191,7 +281,9 @@ impl SyscallInfo {
                 })
             }
             libc::SYS_bind => {
-                let sock_addr = tracee.memcpy_struct::<sockaddr>(args[1])?;
+                let sock_addr = tracee
+                    .memcpy_struct::<sockaddr>(args[1])
+                    .unwrap_or_default();
                 tracee.parse_return(SyscallDisc::Bind)?;
                 Ok(SyscallInfo::Bind {
                     sockfd: args[0] as libc::c_int,
@@ -209,10 +301,10 @@ impl SyscallInfo {
             libc::SYS_clone => {
                 // this is ONLY compatible with x86-64 and some other weird ass architectures
                 let clone = SyscallInfo::Clone {
-                    flags: args[0],
-                    stack: args[1].try_into().unwrap(),
-                    parent_tid: args[2].try_into().unwrap(),
-                    child_tid: args[3].try_into().unwrap(),
+                    flags: CloneFlags(args[0]),
+                    stack: args[1] as usize,
+                    parent_tid: args[2] as usize,
+                    child_tid: args[3] as usize,
                     tls: args[4],
                 };
                 tracee.parse_return(SyscallDisc::Clone)?;
@@ -233,58 +325,105 @@ impl SyscallInfo {
                 // }
             }
             libc::SYS_execve => {
-                let pathname = tracee.strcpy(args[0])?;
-                bytes_as_string(&pathname);
+                let pathname = capture_path(tracee, args[0]);
+                bytes_as_string(&pathname.data);
                 // when the process was called and what was its cmdline
-                let argv = tracee.strcpy(args[1])?;
-                let envp = tracee.strcpy(args[2])?;
-                tracee.syscall()?;
-                // tracee.parse_return(SyscallDisc::Execve)?;
-                // Ok(Syscall::Execve { pathname,  argv, envp })
-                if let WaitEvents::Exec = tracee.wait_for_stop()? {
-                    tracee.parse_return(SyscallDisc::Execve)?;
-                    Ok(SyscallInfo::Execve {
-                        pathname,
-                        argv,
-                        envp,
-                    })
-                } else {
-                    let return_value = tracee.read_rax()?;
-                    Err(TraceErrEvt::Event(TraceEvent::SyscallError {
-                        syscall: SyscallDisc::Execve,
-                        error: parse_syscall_error(return_value),
-                        rip: syscall_info.instruction_pointer,
-                        cpu_time: tracee.get_cpu_time()
-                    }))
-                }
+                let argv = tracee.capture_string_array(args[1]).unwrap_or_default();
+                let envp = tracee.capture_string_array(args[2]).unwrap_or_default();
+                // a successful exec is reported by an exec event before the syscall-exit stop, it's
+                // remembered by the tracee and reported by SyscallIter after this syscall
+                tracee.parse_return(SyscallDisc::Execve)?;
+                Ok(SyscallInfo::Execve {
+                    pathname,
+                    argv,
+                    envp,
+                })
+            }
+            libc::SYS_execveat => {
+                let pathname = capture_path(tracee, args[1]);
+                bytes_as_string(&pathname.data);
+                let argv = tracee.capture_string_array(args[2]).unwrap_or_default();
+                let envp = tracee.capture_string_array(args[3]).unwrap_or_default();
+                tracee.parse_return(SyscallDisc::Execveat)?;
+                Ok(SyscallInfo::Execveat {
+                    dirfd: args[0] as libc::c_int,
+                    pathname,
+                    argv,
+                    envp,
+                    flags: AtFlags(args[4] as libc::c_int),
+                })
             }
             libc::SYS_exit_group => Ok(SyscallInfo::ExitGroup {
                 status: args[0] as libc::c_int,
             }),
             libc::SYS_openat => {
-                let pathname = tracee.strcpy(args[1])?;
+                let pathname = capture_path(tracee, args[1]);
                 let opened_fd = tracee.parse_return(SyscallDisc::Openat)? as libc::c_int;
                 Ok(SyscallInfo::Openat {
                     dirfd: args[0] as libc::c_int,
                     pathname,
-                    flags: fcntl::OFlag::from_bits(args[2] as libc::c_int).unwrap(),
-                    mode: stat::Mode::from_bits(args[3] as libc::mode_t).unwrap(),
+                    flags: OpenFlags(args[2] as libc::c_int),
+                    mode: FileMode(args[3] as libc::mode_t),
                     opened_fd,
                 })
             }
             libc::SYS_unlink => {
-                let pathname = tracee.strcpy(args[1])?;
+                let pathname = capture_path(tracee, args[0]);
                 tracee.parse_return(SyscallDisc::Unlink)?;
                 Ok(SyscallInfo::Unlink { pathname })
             }
+            libc::SYS_recvfrom => {
+                let received = tracee.parse_return(SyscallDisc::Recvfrom)?;
+                let received_bytes = tracee
+                    .capture(args[1], received as usize)
+                    .unwrap_or_else(|_| CapturedBytes::unreadable(received as usize));
+                Ok(SyscallInfo::Recvfrom {
+                    sockfd: args[0] as libc::c_int,
+                    received_bytes,
+                    requested_count: args[2] as usize,
+                    flags: RecvFlags(args[3] as libc::c_int),
+                })
+            }
+            libc::SYS_getrandom => {
+                let generated = tracee.parse_return(SyscallDisc::Getrandom)?;
+                let random_bytes = tracee
+                    .capture(args[0], generated as usize)
+                    .unwrap_or_else(|_| CapturedBytes::unreadable(generated as usize));
+                Ok(SyscallInfo::Getrandom {
+                    random_bytes,
+                    requested_count: args[1] as usize,
+                    flags: RandomFlags(args[2] as libc::c_uint),
+                })
+            }
+            libc::SYS_clock_gettime => {
+                tracee.parse_return(SyscallDisc::ClockGettime)?;
+                let time = tracee
+                    .memcpy_struct::<libc::timespec>(args[1])
+                    .unwrap_or_default()
+                    .map(|time| Duration::new(time.tv_sec.cast_unsigned(), time.tv_nsec as u32));
+                Ok(SyscallInfo::ClockGettime {
+                    clock_id: args[0] as libc::c_int,
+                    time,
+                })
+            }
             _ => {
-                warn!("Unknown syscall was called");
+                let signature = table::lookup(syscall_number);
+                if let Some(signature) = signature {
+                    warn!("Undecoded syscall {} was called", signature.name);
+                } else {
+                    warn!("Unknown syscall {syscall_number} was called");
+                }
+                // arguments need to be read before the kernel changes them
+                let decoded_args = signature
+                    .map(|signature| signature.decode_args(tracee, &args))
+                    .unwrap_or_default();
                 tracee.syscall()?;
-                tracee.wait_for_stop()?;
+                tracee.wait_for_syscall_stop()?;
                 let return_value = tracee.read_rax()?;
                 Ok(SyscallInfo::Unknown {
                     id: syscall_number,
                     args,
+                    decoded_args,
                     return_value,
                 })
             }
@@ -292,38 +431,193 @@ impl SyscallInfo {
     }
 }
 
+/// Wall-clock timing of a single syscall
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct SyscallTime {
+    /// `CLOCK_MONOTONIC` time of the syscall-entry stop
+    pub entry: Duration,
+    /// `CLOCK_MONOTONIC` time of the syscall-exit stop, same as `entry` for syscalls that never
+    /// return
+    pub exit: Duration,
+    /// `CLOCK_REALTIME` time (since Unix epoch) of the syscall-entry stop, if it was requested
+    pub realtime_entry: Option<Duration>,
+}
+
+impl SyscallTime {
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.exit.saturating_sub(self.entry)
+    }
+}
+
+/// Location of an instruction in the tracee, relative to the module it's mapped from
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct CallSite {
+    /// Virtual address in the tracee
+    pub address: u64,
+    /// Path of the mapped file or a pseudo-path like `[vdso]`, `None` for anonymous memory and
+    /// addresses outside of any mapping
+    pub module: Option<String>,
+    /// GNU build-id of the module, if it's an ELF file with one
+    pub build_id: Option<Vec<u8>>,
+    /// Offset in the module file, or from the start of the mapping if it's not a file
+    pub offset: u64,
+}
+
 #[derive(PartialEq, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Syscall {
     pub syscall: SyscallInfo,
     pub cpu_time: f64,
-    // TODO this is the ELF address, but it should not be linked to 
-    pub virt_addr_offset: usize,
+    pub time: SyscallTime,
+    /// Where the syscall instruction is
+    pub call_site: CallSite,
+    /// Return addresses of the callers, innermost first, empty unless enabled by
+    /// [`SyscallIterOpts::stack_depth`]
+    pub stack: Vec<CallSite>,
 }
 
 impl Syscall {
-    pub fn parse(tracee: &mut Tracee) -> Result<Syscall, TraceErrEvt> {
-        tracee.wait_for_stop()?;
-        let cpu_time = tracee.get_cpu_time();
-        let syscall_info: PtraceSyscallInfo = tracee.syscall_info()?.into();
-        let syscall = SyscallInfo::parse_until_return(tracee, syscall_info)?;
-
-        let addr = tracee
-                .translate_address_from_virtual(
-                    syscall_info.instruction_pointer.try_into().unwrap(),
-                )
-                .unwrap()
-                .unwrap();
+    /// Parses the next syscall matching `filter`, syscalls that don't match are skipped
+    ///
+    /// Rules of `injector` are applied to all syscalls, even those not matching `filter`. Events
+    /// of held delays and tampered results are collected in `injector`. Hooks of `handler` are
+    /// called at syscall entry and at other stops, but not at syscall exit.
+    pub fn parse(
+        tracee: &mut Tracee,
+        filter: &SyscallFilter,
+        injector: &mut Injector,
+        handler: &mut dyn TraceHandler,
+    ) -> Result<Syscall, TraceErrEvt> {
+        let (syscall_info, exit_delay) = loop {
+            match tracee.wait_for_stop()? {
+                WaitEvents::Syscall | WaitEvents::Seccomp if tracee.is_tracing() => (),
+                // in seccomp mode exec is never stopped on, only its event is reported
+                WaitEvents::Exec if tracee.take_exec() => {
+                    return Err(TraceErrEvt::Event(exec_event(tracee)?));
+                }
+                // e.g. signal-delivery-stop, the signal is passed to the tracee when it's resumed
+                event => {
+                    trace!("resuming tracee after non-syscall stop");
+                    handle_stop(tracee, handler, &event)?;
+                    tracee.resume()?;
+                    continue;
+                }
+            }
+            let mut syscall_info: PtraceSyscallInfo = tracee.syscall_info()?.into();
+            if let Some(PtraceSyscallInfoData::Seccomp {
+                syscall_number,
+                args,
+                ..
+            }) = syscall_info.data
+            {
+                syscall_info.data = Some(PtraceSyscallInfoData::Entry {
+                    syscall_number,
+                    args,
+                });
+            }
+            let Some(PtraceSyscallInfoData::Entry {
+                syscall_number,
+                args,
+            }) = syscall_info.data
+            else {
+                break (syscall_info, None);
+            };
+            let exit_delay = injector.enter_syscall(tracee, syscall_number, &args)?;
+            if !filter.matches(syscall_number) {
+                trace!("not recording syscall {syscall_number}");
+                tracee.resume_unrecorded()?;
+                injector.exit_syscall(tracee, syscall_number, exit_delay)?;
+                if tracee.take_exec() {
+                    return Err(TraceErrEvt::Event(exec_event(tracee)?));
+                }
+                tracee.resume()?;
+                continue;
+            }
+            let entry = SyscallEntry {
+                number: syscall_number,
+                args,
+                instruction_pointer: syscall_info.instruction_pointer,
+            };
+            if let Some(args) = enter_hook(tracee, handler, injector, &entry, exit_delay)? {
+                syscall_info.data = Some(PtraceSyscallInfoData::Entry {
+                    syscall_number,
+                    args,
+                });
+            }
+            break (
+                syscall_info,
+                exit_delay.map(|delay| (syscall_number, delay)),
+            );
+        };
+        tracee.begin_syscall();
+        let cpu_time = tracee.get_cpu_time()?;
+        // before the syscall returns, a successful exec replaces the mappings
+        let mappings = tracee
+            .mappings()
+            .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+        let call_site = tracee.resolve_call_site(&mappings, syscall_info.instruction_pointer);
+        let stack = tracee.capture_stack(&mappings)?;
+        let syscall = match syscall_info.data {
+            Some(PtraceSyscallInfoData::Entry {
+                syscall_number,
+                args,
+            }) => injector.replay(tracee, syscall_number, &args),
+            _ => Ok(None),
+        };
+        let syscall = match syscall {
+            Ok(Some(syscall)) => Ok(syscall),
+            Ok(None) => SyscallInfo::parse_until_return(tracee, syscall_info),
+            Err(err) => Err(err),
+        };
+        let time = tracee.syscall_time();
+        if syscall.as_ref().is_ok_and(|info| !info.is_exit_group()) {
+            tracee.apply_tampering()?;
+        }
+        // the tracee is still at the exit stop, unless it exited
+        if let Some((id, delay)) = exit_delay
+            && matches!(
+                syscall,
+                Ok(_) | Err(TraceErrEvt::Event(TraceEvent::SyscallError { .. }))
+            )
+        {
+            injector.exit_syscall(tracee, id, Some(delay))?;
+        } else if let Some(event) = tracee.take_tampered() {
+            injector.push_event(event);
+        }
+        let syscall = syscall?;
+
         Ok(Self {
             syscall,
             cpu_time,
-            virt_addr_offset: addr,
+            time,
+            call_site,
+            stack,
         })
     }
 }
 
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "these are independent options"
+)]
 pub struct SyscallIterOpts {
-    skip_to_main: bool,
+    pub(crate) skip_to_main: bool,
+    pub(crate) skip_to_main_after_exec: bool,
     kill_on_exit: bool,
+    pub(crate) realtime_timestamps: bool,
+    pub(crate) filter: SyscallFilter,
+    pub(crate) seccomp: bool,
+    pub(crate) capture_limits: CaptureLimits,
+    redactor: Option<Redactor>,
+    pub(crate) stack_depth: usize,
+    windows: Vec<TraceWindow>,
+    pub(crate) injector: Injector,
 }
 
 impl SyscallIterOpts {
@@ -332,26 +626,130 @@ impl SyscallIterOpts {
         self.skip_to_main = value;
         self
     }
+    /// Also skip to `main` of every program the tracee executes
+    #[must_use]
+    pub fn skip_to_main_after_exec(mut self, value: bool) -> Self {
+        self.skip_to_main_after_exec = value;
+        self
+    }
     #[must_use]
     pub fn kill_on_exit(mut self, value: bool) -> Self {
         self.kill_on_exit = value;
         self
     }
+    /// Record realtime (Unix epoch) timestamps next to the monotonic ones
+    #[must_use]
+    pub fn realtime_timestamps(mut self, value: bool) -> Self {
+        self.realtime_timestamps = value;
+        self
+    }
+    /// Only parse and return syscalls matching `filter`
+    #[must_use]
+    pub fn filter(mut self, filter: SyscallFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+    /// Only stop the tracee on syscalls selected by its seccomp filter
+    ///
+    /// The tracee has to be spawned by [`seccomp::spawn_ptrace`](crate::seccomp::spawn_ptrace),
+    /// ideally with the same filter as set by [`SyscallIterOpts::filter`], which
+    /// [`Tracer`](crate::tracer::Tracer) does.
+    #[must_use]
+    pub fn seccomp(mut self, value: bool) -> Self {
+        self.seccomp = value;
+        self
+    }
+    /// Limit how much data is copied from tracee's memory, see [`CaptureLimits::NO_DATA`]
+    #[must_use]
+    pub fn capture_limits(mut self, limits: CaptureLimits) -> Self {
+        self.capture_limits = limits;
+        self
+    }
+    /// Capture up to `depth` return addresses of the callers of every syscall, 0 disables it
+    ///
+    /// The stack is walked using frame pointers, so only frames of code compiled with them are
+    /// found, the walk stops at the first frame without one.
+    #[must_use]
+    pub fn stack_depth(mut self, depth: usize) -> Self {
+        self.stack_depth = depth;
+        self
+    }
+    /// Redact secrets from syscalls before they are returned
+    #[must_use]
+    pub fn redact(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+    /// Only trace syscalls made inside `window`, can be called multiple times
+    ///
+    /// Windows are resolved after skipping to `main`, so functions of shared libraries can be
+    /// used only together with [`SyscallIterOpts::skip_to_main`].
+    #[must_use]
+    pub fn window(mut self, window: TraceWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+    /// Make syscalls matching `fault` fail without running them, they are reported as
+    /// [`TraceEvent::FaultInjected`] instead, can be called multiple times
+    ///
+    /// With [`SyscallIterOpts::seccomp`], the seccomp filter has to stop on the faulted syscalls.
+    #[must_use]
+    pub fn inject_fault(mut self, fault: Fault) -> Self {
+        self.injector.add_fault(fault);
+        self
+    }
+    /// Hold the tracee at the entry or exit of syscalls matching `delay`, can be called multiple
+    /// times
+    ///
+    /// Every delay is reported as [`TraceEvent::DelayInjected`] before the record of its
+    /// syscall and isn't counted in the syscall's [`SyscallTime`].
+    #[must_use]
+    pub fn inject_delay(mut self, delay: Delay) -> Self {
+        self.injector.add_delay(delay);
+        self
+    }
+    /// Rewrite results of successful syscalls matching `tamper`, can be called multiple times
+    ///
+    /// Every change is reported as [`TraceEvent::Tampered`] before the record of its syscall,
+    /// the record itself keeps what the kernel returned. With [`SyscallIterOpts::seccomp`], the
+    /// seccomp filter has to stop on the tampered syscalls.
+    #[must_use]
+    pub fn tamper(mut self, tamper: Tamper) -> Self {
+        self.injector.add_tamper(tamper);
+        self
+    }
+    /// Feed recorded results back to the tracee and report where it diverges from the
+    /// recording, see [`replay`]
+    #[must_use]
+    pub fn replay(mut self, replay: Replay) -> Self {
+        self.injector.set_replay(replay);
+        self
+    }
 }
 
 impl Default for SyscallIterOpts {
     fn default() -> Self {
         Self {
             skip_to_main: true,
+            skip_to_main_after_exec: false,
             kill_on_exit: true,
+            realtime_timestamps: false,
+            filter: SyscallFilter::all(),
+            seccomp: false,
+            capture_limits: CaptureLimits::default(),
+            redactor: None,
+            stack_depth: 0,
+            windows: vec![],
+            injector: Injector::default(),
         }
     }
 }
 
 #[derive(Debug, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[repr(u8)]
 pub enum TraceData {
-    Event(TraceEvent),
-    Syscall(Syscall),
+    Event(TraceEvent) = 0,
+    Syscall(Syscall) = 1,
 }
 
 // TODO using Try trait could make this conversion not necessary for the design I want
@@ -373,11 +771,27 @@ impl TryFrom<Result<Syscall, TraceErrEvt>> for TraceData {
 pub enum SyscallIterError {
     #[error("error returned from ptrace")]
     PtraceError(#[from] Errno),
+    #[error("could not skip to main: {0}")]
+    SkipToMain(#[from] TraceError),
+    #[error("function {0} of a trace window was not found in the tracee")]
+    FunctionNotFound(String),
+    #[error("tracing thread could not be started or panicked")]
+    ThreadError,
     // #[error("error returned from IO libraries")]
     // IOError(#[from] io::Error),
 }
 
-pub struct SyscallIter(Tracee);
+pub struct SyscallIter {
+    tracee: Tracee,
+    filter: SyscallFilter,
+    skip_to_main_after_exec: bool,
+    redactor: Option<Redactor>,
+    injector: Injector,
+    /// Data returned before parsing the next syscall
+    pending: VecDeque<Result<TraceData, TraceError>>,
+    /// Nothing is traced anymore, see [`Action::Detach`]
+    detached: bool,
+}
 
 impl SyscallIter {
     pub fn new(mut tracee: Tracee, opts: &SyscallIterOpts) -> Result<Self, SyscallIterError> {
@@ -389,32 +803,370 @@ impl SyscallIter {
         if opts.kill_on_exit {
             options |= Options::PTRACE_O_EXITKILL;
         }
+        if opts.seccomp {
+            // threads and children have to be traced too, see Tracee::wait_for_tracee
+            options |= Options::PTRACE_O_TRACESECCOMP
+                | Options::PTRACE_O_TRACECLONE
+                | Options::PTRACE_O_TRACEFORK
+                | Options::PTRACE_O_TRACEVFORK;
+        }
         tracee.setoptions(options)?;
+        tracee.record_realtime(opts.realtime_timestamps);
+        tracee.use_seccomp(opts.seccomp);
+        tracee.set_capture_limits(opts.capture_limits);
+        tracee.set_stack_depth(opts.stack_depth);
         if opts.skip_to_main {
-            // this file does always exist, it can be guarded with a permission but that's a good
-            // reason for failure anyway
-            let file = std::fs::read("/proc/".to_owned() + &tracee.get_pid_string() + "/exe")
-                .expect("could not access proc_pid_exe file");
-            let elf = ElfBytes::<AnyEndian>::minimal_parse(file.as_slice()).unwrap();
-            let entry_point = elf.ehdr.e_entry;
-
-            // address needs to exist
-            let main_address = tracee
-                .translate_address_to_virtual(entry_point as usize)
-                .expect("could not access proc_pid_maps file")
-                .unwrap();
-            trace!("creating hardware breakpoint on main() at address {main_address:#X}");
-            tracee.add_local_breakpoint(main_address)?;
-
-            tracee.cont()?;
-            match tracee.wait_for_stop() {
-                Ok(WaitEvents::Stopped(Signal::SIGTRAP)) => {
-                    tracee.check_and_remove_break()?;
+            skip_to_main(&mut tracee)?;
+        }
+        for window in &opts.windows {
+            add_window(&mut tracee, window)?;
+        }
+        Ok(Self {
+            tracee,
+            filter: opts.filter.clone(),
+            skip_to_main_after_exec: opts.skip_to_main_after_exec,
+            redactor: opts.redactor.clone(),
+            injector: opts.injector.clone(),
+            pending: VecDeque::new(),
+            detached: false,
+        })
+    }
+}
+
+/// Runs the tracee until the `main` of its current program
+///
+/// `main` is looked up in `.symtab` of the executable. Stripped programs are run to the
+/// `__libc_start_main` of their libc, which gets `main` as its first argument. If there's no
+/// `__libc_start_main` either, the tracee is only run to the entry point of the executable.
+fn skip_to_main(tracee: &mut Tracee) -> Result<(), TraceError> {
+    let (path, file) = read_executable(tracee)?;
+    let elf = ElfBytes::<AnyEndian>::minimal_parse(file.as_slice())
+        .map_err(|_| TraceError::InvalidExecutable)?;
+    let mappings = tracee
+        .mappings()
+        .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+    let runtime_address = |address| {
+        maps::runtime_address(&mappings, &path, &elf, address)
+            .ok_or(TraceError::UnmappedAddress(address))
+    };
+
+    if let Some(main) = maps::find_function(&elf, "main") {
+        trace!("skipping to main() from .symtab");
+        return run_to_breakpoint(tracee, runtime_address(main)?);
+    }
+    // shared libraries are mapped only by the time the entry point is reached
+    run_to_breakpoint(tracee, runtime_address(elf.ehdr.e_entry)?)?;
+    let Some(libc_start_main) = find_loaded_function(tracee, "__libc_start_main")? else {
+        warn!("could not find main() or __libc_start_main(), tracing from the entry point");
+        return Ok(());
+    };
+    trace!("skipping to main() passed to __libc_start_main()");
+    run_to_breakpoint(tracee, libc_start_main)?;
+    let main = tracee.getregs()?.rdi;
+    run_to_breakpoint(tracee, main)
+}
+
+/// Path of the program the tracee is running, as shown in its mappings, and its content
+fn read_executable(tracee: &Tracee) -> Result<(String, Vec<u8>), TraceError> {
+    let procfs_error = |err: io::Error| TraceError::ProcfsError(err.kind());
+    let path = tracee.executable().map_err(procfs_error)?;
+    // the executable could have been replaced on disk, the one in procfs is the running one
+    let file =
+        std::fs::read(format!("/proc/{}/exe", tracee.get_pid_string())).map_err(procfs_error)?;
+    Ok((path.to_string_lossy().into_owned(), file))
+}
+
+/// Address of function `name` in any executable module of the tracee
+fn find_loaded_function(tracee: &Tracee, name: &str) -> Result<Option<u64>, TraceError> {
+    let mappings = tracee
+        .mappings()
+        .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+    let mut modules = mappings
+        .iter()
+        .filter(|mapping| mapping.is_file() && mapping.permissions.contains('x'))
+        .filter_map(|mapping| mapping.pathname.as_deref())
+        .collect::<Vec<_>>();
+    modules.dedup();
+    for module in modules {
+        let Ok(file) = std::fs::read(module) else {
+            continue;
+        };
+        let Ok(elf) = ElfBytes::<AnyEndian>::minimal_parse(file.as_slice()) else {
+            continue;
+        };
+        if let Some(address) = maps::find_function(&elf, name) {
+            return Ok(maps::runtime_address(&mappings, module, &elf, address));
+        }
+    }
+    Ok(None)
+}
+
+/// Resolves `window` in the current program of the tracee and sets it
+fn add_window(tracee: &mut Tracee, window: &TraceWindow) -> Result<(), SyscallIterError> {
+    match window {
+        TraceWindow::Function(name) => {
+            let start = find_loaded_function(tracee, name)?
+                .ok_or_else(|| SyscallIterError::FunctionNotFound(name.clone()))?;
+            tracee.add_window(start, None)?;
+        }
+        TraceWindow::Addresses { start, end } => {
+            let (path, file) = read_executable(tracee)?;
+            let elf = ElfBytes::<AnyEndian>::minimal_parse(file.as_slice())
+                .map_err(|_| TraceError::InvalidExecutable)?;
+            let mappings = tracee
+                .mappings()
+                .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+            let runtime_address = |address| {
+                maps::runtime_address(&mappings, &path, &elf, address)
+                    .ok_or(TraceError::UnmappedAddress(address))
+            };
+            let (start, end) = (runtime_address(*start)?, runtime_address(*end)?);
+            tracee.add_window(start, Some(end))?;
+        }
+    }
+    Ok(())
+}
+
+/// Continues the tracee until it executes the instruction at `address`
+fn run_to_breakpoint(tracee: &mut Tracee, address: u64) -> Result<(), TraceError> {
+    tracee.add_breakpoint(address)?;
+    loop {
+        tracee.cont()?;
+        match tracee.wait_for_stop() {
+            Ok(WaitEvents::Breakpoint(hit)) if hit == address => break,
+            // the seccomp filter also stops on syscalls before the breakpoint, other signals
+            // are delivered by the next cont
+            Ok(
+                WaitEvents::Seccomp
+                | WaitEvents::Fork
+                | WaitEvents::Clone
+                | WaitEvents::Stopped(_)
+                | WaitEvents::Breakpoint(_),
+            ) => (),
+            Ok(WaitEvents::Syscall | WaitEvents::Exec) | Err(TraceErrEvt::Event(_)) => {
+                return Err(TraceError::BreakpointNotReached(address));
+            }
+            Err(TraceErrEvt::Error(err)) => return Err(err),
+        }
+    }
+    tracee.remove_breakpoint(address)?;
+    Ok(())
+}
+
+/// Calls `handler` at a stop which isn't a syscall, like a signal-delivery-stop
+fn handle_stop(
+    tracee: &mut Tracee,
+    handler: &mut dyn TraceHandler,
+    event: &WaitEvents,
+) -> Result<(), TraceErrEvt> {
+    let action = match *event {
+        WaitEvents::Stopped(signal) => {
+            trace!("tracee will receive signal {signal:?}");
+            handler.on_signal(tracee, signal)
+        }
+        WaitEvents::Fork | WaitEvents::Clone => {
+            let child = tracee.new_child()?;
+            handler.on_fork(tracee, child)
+        }
+        _ => Action::Continue,
+    };
+    match action {
+        Action::Skip(_) if matches!(event, WaitEvents::Stopped(_)) => {
+            trace!("handler suppressed the signal");
+            tracee.suppress_signal();
+        }
+        Action::Detach => return Err(detach(tracee)?),
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Calls the syscall entry hook of `handler`, returns the arguments the syscall runs with if
+/// they were changed
+fn enter_hook(
+    tracee: &mut Tracee,
+    handler: &mut dyn TraceHandler,
+    injector: &mut Injector,
+    entry: &SyscallEntry,
+    exit_delay: Option<Duration>,
+) -> Result<Option<[u64; 6]>, TraceErrEvt> {
+    match handler.on_syscall_enter(tracee, entry) {
+        Action::Skip(return_value) => {
+            debug!("handler skipped syscall {}", entry.number);
+            tracee.begin_syscall();
+            tracee.emulate_syscall(return_value, None)?;
+            let time = tracee.syscall_time();
+            injector.exit_syscall(tracee, entry.number, exit_delay)?;
+            Err(TraceErrEvt::Event(TraceEvent::SyscallSkipped {
+                id: entry.number,
+                return_value,
+                time,
+            }))
+        }
+        Action::SetArgs(args) => {
+            tracee.set_args(&args)?;
+            Ok(Some(args))
+        }
+        Action::Detach => Err(detach(tracee)?),
+        Action::Continue | Action::SetReturnValue(_) => Ok(None),
+    }
+}
+
+/// Detaches from the tracee, returns the event ending the trace
+fn detach(tracee: &mut Tracee) -> Result<TraceErrEvt, TraceError> {
+    debug!("detaching from the tracee");
+    tracee.detach()?;
+    Ok(TraceErrEvt::Event(TraceEvent::Detached))
+}
+
+/// Builds the event of an exec the tracee just did
+fn exec_event(tracee: &Tracee) -> Result<TraceEvent, TraceError> {
+    let executable = tracee
+        .executable()
+        .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+    Ok(TraceEvent::Exec {
+        executable: executable.into_os_string().into_vec(),
+    })
+}
+
+impl SyscallIter {
+    /// Returns the next syscall or event like [`Iterator::next`], calling hooks of `handler`
+    /// on the way
+    pub fn next_with(
+        &mut self,
+        handler: &mut dyn TraceHandler,
+    ) -> Option<Result<TraceData, TraceError>> {
+        if let Some(data) = self.pending.pop_front() {
+            return Some(data);
+        }
+        if self.detached {
+            return None;
+        }
+        let mut data = if self.tracee.take_exec() {
+            // the syscall record of the exec was returned by the last call
+            exec_event(&self.tracee).map(TraceData::Event)
+        } else {
+            match self.tracee.resume() {
+                Err(Errno::ESRCH) => {
+                    return None;
                 }
-                _ => panic!(),
+                Err(err) => return Some(Err(err.into())),
+                _ => (),
             }
+            TraceData::try_from(Syscall::parse(
+                &mut self.tracee,
+                &self.filter,
+                &mut self.injector,
+                handler,
+            ))
+        };
+        if let Ok(TraceData::Event(TraceEvent::Exec { .. })) = data
+            && self.skip_to_main_after_exec
+            && let Err(err) = skip_to_main(&mut self.tracee)
+        {
+            return Some(Err(err));
+        }
+        if let (Ok(data), Some(redactor)) = (&mut data, &mut self.redactor) {
+            redactor.redact_data(data);
+        }
+        let detach = match &data {
+            Ok(data) => match self.call_handler(handler, data) {
+                Ok(detach) => detach,
+                Err(err) => return Some(Err(err)),
+            },
+            Err(_) => false,
+        };
+        let events = self.injector.take_events();
+        if events.is_empty() && !detach {
+            return Some(data);
         }
-        Ok(Self(tracee))
+        self.pending
+            .extend(events.into_iter().map(|event| Ok(TraceData::Event(event))));
+        self.pending.push_back(data);
+        if detach {
+            self.pending
+                .push_back(Ok(TraceData::Event(TraceEvent::Detached)));
+        }
+        self.pending.pop_front()
+    }
+
+    /// Stops tracing, the tracee continues untraced
+    ///
+    /// Does nothing if the tracee already exited or was detached.
+    pub fn detach(mut self) -> Result<(), TraceError> {
+        if self.detached {
+            return Ok(());
+        }
+        match self.tracee.detach() {
+            Ok(()) | Err(Errno::ESRCH) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Traces until the tracee exits or is detached, calling hooks of `handler`
+    ///
+    /// Returns the first error, the syscalls and events are only passed to the handler.
+    pub fn run(mut self, handler: &mut dyn TraceHandler) -> Result<(), TraceError> {
+        while let Some(data) = self.next_with(handler) {
+            data?;
+        }
+        Ok(())
+    }
+
+    /// Calls the hooks of `handler` which get `data`, returns `true` if the tracee was detached
+    fn call_handler(
+        &mut self,
+        handler: &mut dyn TraceHandler,
+        data: &TraceData,
+    ) -> Result<bool, TraceError> {
+        let tracee = &mut self.tracee;
+        let action = match data {
+            // exit_group never returns
+            TraceData::Syscall(syscall) if syscall.syscall.is_exit_group() => Action::Continue,
+            TraceData::Syscall(syscall) => handler.on_syscall_exit(tracee, Ok(syscall)),
+            TraceData::Event(event @ TraceEvent::SyscallError { .. }) => {
+                handler.on_syscall_exit(tracee, Err(event))
+            }
+            TraceData::Event(TraceEvent::Exec { executable }) => {
+                handler.on_exec(tracee, executable)
+            }
+            TraceData::Event(
+                event @ (TraceEvent::ProcessExit(_) | TraceEvent::Terminated { .. }),
+            ) => {
+                handler.on_exit(tracee, event);
+                Action::Continue
+            }
+            TraceData::Event(TraceEvent::Detached) => {
+                self.detached = true;
+                Action::Continue
+            }
+            TraceData::Event(_) => Action::Continue,
+        };
+        match action {
+            Action::SetReturnValue(tampered)
+                if matches!(
+                    data,
+                    TraceData::Syscall(_) | TraceData::Event(TraceEvent::SyscallError { .. })
+                ) =>
+            {
+                let id = tracee.getregs()?.orig_rax;
+                let original = tracee.read_rax()?;
+                tracee.set_return_value(tampered)?;
+                self.injector.push_event(TraceEvent::Tampered {
+                    id,
+                    original,
+                    tampered,
+                    written: 0,
+                });
+            }
+            Action::Detach => {
+                tracee.detach()?;
+                self.detached = true;
+                return Ok(true);
+            }
+            _ => (),
+        }
+        Ok(false)
     }
 }
 
@@ -422,19 +1174,7 @@ impl Iterator for SyscallIter {
     type Item = Result<TraceData, TraceError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.syscall() {
-            Err(Errno::ESRCH) => {
-                return None;
-            }
-            Err(err) => return Some(Err(err.into())),
-            _ => (),
-        }
-        Some(TraceData::try_from(Syscall::parse(&mut self.0)))
-        // Some(Syscall::parse(&mut self.0).try_into())
-        // match Syscall::parse(&mut self.0) {
-        //     Ok(call) => Some(Ok(call)),
-        //     Err(err) => Some(Err(err)),
-        // }
+        self.next_with(&mut ())
     }
 }
 
@@ -445,3 +1185,15 @@ fn bytes_as_string(bytes: &[u8]) {
         debug!("bytes as string: {text:?}");
     }
 }
+
+/// Captures a path argument, a path which can't be read is recorded as
+/// [`CapturedBytes::unreadable`]
+///
+/// Arguments are captured at the syscall-entry stop, failing there would leave the tracee
+/// stopped before the exit the next record expects. A bad pointer makes the syscall itself
+/// fail with `EFAULT`.
+fn capture_path(tracee: &mut Tracee, base: u64) -> CapturedBytes {
+    tracee
+        .capture_string(base)
+        .unwrap_or_else(|_| CapturedBytes::unreadable(0))
+}
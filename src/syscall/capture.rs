@@ -0,0 +1,92 @@
+use crate::syscall::NewTypeSer;
+
+/// Hard limit of bytes read from a single C string, strings longer than this are always
+/// truncated
+pub const MAX_BYTES_CSTRING: usize = 2048 * 1024; // 2 MiB
+
+/// Data copied from tracee's memory, which might be only a part of the original data
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+pub struct CapturedBytes {
+    pub data: Vec<u8>,
+    /// Length of the data in tracee's memory, including the NUL terminator for strings
+    pub original_len: usize,
+    /// `data` contains only first part of the original data, because of [`CaptureLimits`] or
+    /// because the memory couldn't be read
+    pub truncated: bool,
+    /// Parts of `data` were replaced by a [`Redactor`](crate::redact::Redactor)
+    pub redacted: bool,
+}
+
+impl CapturedBytes {
+    /// Truncates `data` to at most `limit` bytes, marking it as truncated if needed
+    #[must_use]
+    pub fn new(mut data: Vec<u8>, limit: usize) -> Self {
+        let original_len = data.len();
+        data.truncate(limit);
+        Self {
+            truncated: data.len() < original_len,
+            data,
+            original_len,
+            redacted: false,
+        }
+    }
+
+    /// Data which couldn't be read from tracee's memory, like a buffer behind a bad pointer
+    ///
+    /// `original_len` is the length passed to the syscall, 0 for strings.
+    #[must_use]
+    pub fn unreadable(original_len: usize) -> Self {
+        Self {
+            data: vec![],
+            original_len,
+            truncated: true,
+            redacted: false,
+        }
+    }
+
+    /// Captured data, if it's a complete string, the NUL terminator is removed
+    #[must_use]
+    pub fn without_nul(&self) -> &[u8] {
+        match self.data.split_last() {
+            Some((0, rest)) if !self.truncated => rest,
+            _ => &self.data,
+        }
+    }
+}
+
+/// Limits of data copied from tracee's memory for each syscall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct CaptureLimits {
+    /// Max bytes captured from a single buffer, like data of `read` and `write`
+    #[rkyv(with = NewTypeSer)]
+    pub max_buffer: usize,
+    /// Max bytes captured from a single C string, like paths, at most [`MAX_BYTES_CSTRING`]
+    #[rkyv(with = NewTypeSer)]
+    pub max_string: usize,
+    /// Max bytes captured by a single syscall from all its buffers and strings
+    #[rkyv(with = NewTypeSer)]
+    pub max_per_syscall: usize,
+}
+
+impl CaptureLimits {
+    /// Don't capture any data, only their lengths are kept
+    pub const NO_DATA: Self = Self {
+        max_buffer: 0,
+        max_string: 0,
+        max_per_syscall: 0,
+    };
+}
+
+impl Default for CaptureLimits {
+    fn default() -> Self {
+        Self {
+            max_buffer: usize::MAX,
+            max_string: MAX_BYTES_CSTRING,
+            max_per_syscall: usize::MAX,
+        }
+    }
+}
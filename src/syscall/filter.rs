@@ -0,0 +1,169 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+use super::table::{self, SYSCALLS, SyscallClass};
+
+/// Syscall numbers above this are not valid on x86-64
+const MAX_SYSCALLS: usize = 512;
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum FilterParseError {
+    #[error("filter expression is empty")]
+    Empty,
+    #[error("unknown syscall '{0}'")]
+    UnknownSyscall(String),
+    #[error("unknown syscall class '%{0}'")]
+    UnknownClass(String),
+    #[error("syscall number {0} is out of range, x86-64 syscalls are below {MAX_SYSCALLS}")]
+    InvalidNumber(u64),
+}
+
+/// Set of syscalls which should be traced, everything else is skipped without being parsed
+///
+/// It's parsed from a comma separated list of syscall names (`openat`), numbers (`257`) and
+/// classes (`%file`, see [`SyscallClass`]), optionally prefixed by `trace=`. `all` and `none`
+/// match every or no syscall. The whole list can be negated with `!`, e.g. `!read,write` traces
+/// everything but reads and writes, the same way as `strace -e trace=` does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallFilter {
+    numbers: [u64; MAX_SYSCALLS / 64],
+}
+
+impl SyscallFilter {
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            numbers: [u64::MAX; MAX_SYSCALLS / 64],
+        }
+    }
+
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            numbers: [0; MAX_SYSCALLS / 64],
+        }
+    }
+
+    #[must_use]
+    pub fn matches(&self, number: u64) -> bool {
+        let Ok(number) = usize::try_from(number) else {
+            return false;
+        };
+        number < MAX_SYSCALLS && self.numbers[number / 64] & (1 << (number % 64)) != 0
+    }
+
+    /// Returns `true` if all syscalls are traced, so the filter doesn't have to be checked
+    #[must_use]
+    pub fn is_all(&self) -> bool {
+        *self == Self::all()
+    }
+
+    /// Numbers of all known syscalls matched by this filter, in ascending order
+    pub fn syscall_numbers(&self) -> impl Iterator<Item = u64> {
+        SYSCALLS
+            .iter()
+            .map(|signature| signature.number)
+            .filter(|number| self.matches(*number))
+    }
+
+    fn insert(&mut self, number: u64) -> Result<(), FilterParseError> {
+        match usize::try_from(number) {
+            Ok(index) if index < MAX_SYSCALLS => {
+                self.numbers[index / 64] |= 1 << (index % 64);
+                Ok(())
+            }
+            _ => Err(FilterParseError::InvalidNumber(number)),
+        }
+    }
+
+    fn insert_item(&mut self, item: &str) -> Result<(), FilterParseError> {
+        if let Some(class) = item.strip_prefix('%') {
+            let class = SyscallClass::from_str(class)
+                .map_err(|_| FilterParseError::UnknownClass(class.to_owned()))?;
+            SYSCALLS
+                .iter()
+                .filter(|signature| signature.has_class(class))
+                .try_for_each(|signature| self.insert(signature.number))
+        } else if let Ok(number) = item.parse::<u64>() {
+            self.insert(number)
+        } else {
+            let signature = table::lookup_name(item)
+                .ok_or_else(|| FilterParseError::UnknownSyscall(item.to_owned()))?;
+            self.insert(signature.number)
+        }
+    }
+
+    fn negate(&mut self) {
+        for bits in &mut self.numbers {
+            *bits = !*bits;
+        }
+    }
+}
+
+impl Default for SyscallFilter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Writes an expression which parses to the same filter, it lists the traced syscalls or, if
+/// there are fewer of them, the syscalls which aren't traced
+impl Display for SyscallFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_all() {
+            return f.write_str("all");
+        }
+        let matched: u32 = self.numbers.iter().map(|bits| bits.count_ones()).sum();
+        if matched == 0 {
+            return f.write_str("none");
+        }
+        let negated = matched as usize * 2 > MAX_SYSCALLS;
+        if negated {
+            f.write_str("!")?;
+        }
+        let numbers = (0..MAX_SYSCALLS as u64).filter(|number| self.matches(*number) != negated);
+        for (i, number) in numbers.enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            match table::syscall_name(number) {
+                Some(name) => f.write_str(name)?,
+                None => write!(f, "{number}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SyscallFilter {
+    type Err = FilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("trace=").unwrap_or(s);
+        let (negated, s) = match s.strip_prefix('!') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        if s.is_empty() {
+            return Err(FilterParseError::Empty);
+        }
+
+        let mut filter = Self::none();
+        for item in s.split(',').map(str::trim) {
+            match item {
+                "all" => filter = Self::all(),
+                "none" => (),
+                _ => filter.insert_item(item)?,
+            }
+        }
+        if negated {
+            filter.negate();
+        }
+        Ok(filter)
+    }
+}
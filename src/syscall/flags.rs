@@ -0,0 +1,255 @@
+//! Flags arguments of syscalls, stored as their raw values
+//!
+//! Kernels keep adding flags, so the raw value is kept as it was passed, including bits which
+//! aren't known to this build. Each type has a decoded view of the known flags, its
+//! [`Display`](std::fmt::Display) implementation prints them like
+//! `O_CREAT | O_WRONLY | unknown 0x40000000`.
+use std::fmt::{self, Debug, Display, Formatter};
+
+use nix::{
+    fcntl::{self, OFlag},
+    sched,
+    sys::{
+        signal::Signal,
+        socket::{MsgFlags, SockFlag},
+        stat::Mode,
+    },
+};
+
+bitflags::bitflags! {
+    /// Flags of `getrandom`, they aren't defined by nix
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct GrndFlags: u32 {
+        const GRND_NONBLOCK = libc::GRND_NONBLOCK;
+        const GRND_RANDOM = libc::GRND_RANDOM;
+        const GRND_INSECURE = libc::GRND_INSECURE;
+    }
+}
+
+/// Writes names of `known` flags and the `unknown` bits, `0` if there are none of them
+fn write_flags<'a>(
+    f: &mut Formatter<'_>,
+    known: impl IntoIterator<Item = &'a str>,
+    unknown: impl fmt::LowerHex + Default + PartialEq + Copy,
+) -> fmt::Result {
+    let mut empty = true;
+    for name in known {
+        if !empty {
+            f.write_str(" | ")?;
+        }
+        f.write_str(name)?;
+        empty = false;
+    }
+    if unknown != Default::default() {
+        if !empty {
+            f.write_str(" | ")?;
+        }
+        write!(f, "unknown {unknown:#x}")?;
+        empty = false;
+    }
+    if empty {
+        f.write_str("0")?;
+    }
+    Ok(())
+}
+
+/// Defines a raw flags type decoded by the `bitflags` type `$flags`
+macro_rules! raw_flags {
+    ($(#[$meta:meta])* $name:ident($bits:ty) => $flags:ty) => {
+        $(#[$meta])*
+        #[derive(
+            Clone, Copy, PartialEq, Eq, Hash, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+        )]
+        #[rkyv(derive(Debug))]
+        pub struct $name(pub $bits);
+
+        impl $name {
+            #[must_use]
+            pub fn bits(self) -> $bits {
+                self.0
+            }
+
+            /// Flags known to this build, without the unknown bits
+            #[must_use]
+            pub fn known(self) -> $flags {
+                <$flags>::from_bits_truncate(self.0)
+            }
+
+            /// Bits which aren't known flags, 0 if there are none
+            #[must_use]
+            pub fn unknown(self) -> $bits {
+                self.0 & !<$flags>::all().bits()
+            }
+        }
+
+        impl From<$flags> for $name {
+            fn from(flags: $flags) -> Self {
+                Self(flags.bits())
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write_flags(
+                    f,
+                    self.known().iter_names().map(|(name, _)| name),
+                    self.unknown(),
+                )
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({self})", stringify!($name))
+            }
+        }
+    };
+}
+
+raw_flags! {
+    /// Flags of `openat`, `O_RDONLY` is 0 so it's never printed
+    OpenFlags(i32) => OFlag
+}
+
+raw_flags! {
+    /// Permission bits of created files
+    FileMode(u32) => Mode
+}
+
+raw_flags! {
+    /// `AT_*` flags, e.g. of `execveat`
+    AtFlags(i32) => fcntl::AtFlags
+}
+
+raw_flags! {
+    /// `MSG_*` flags of `recvfrom`
+    RecvFlags(i32) => MsgFlags
+}
+
+raw_flags! {
+    /// Flags of `getrandom`
+    RandomFlags(u32) => GrndFlags
+}
+
+/// Flags of `clone`, the lowest byte is the signal sent to the parent when the child exits
+#[derive(Clone, Copy, PartialEq, Eq, Hash, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct CloneFlags(pub u64);
+
+impl CloneFlags {
+    const SIGNAL_MASK: u64 = 0xff;
+
+    #[must_use]
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Flags known to this build, without the exit signal and the unknown bits
+    #[must_use]
+    pub fn known(self) -> sched::CloneFlags {
+        // only the lower 32 bits are flags of clone, the upper ones are used by clone3
+        sched::CloneFlags::from_bits_truncate((self.0 & !Self::SIGNAL_MASK) as i32)
+    }
+
+    /// Signal sent to the parent when the child exits, `None` if it's not a valid signal
+    #[must_use]
+    pub fn exit_signal(self) -> Option<Signal> {
+        match self.0 & Self::SIGNAL_MASK {
+            0 => None,
+            signal => Signal::try_from(signal as i32).ok(),
+        }
+    }
+
+    /// Bits which aren't known flags, 0 if there are none
+    #[must_use]
+    pub fn unknown(self) -> u64 {
+        let known = u64::from(sched::CloneFlags::all().bits().cast_unsigned());
+        let signal = if self.exit_signal().is_some() {
+            Self::SIGNAL_MASK
+        } else {
+            0
+        };
+        self.0 & !(known | signal)
+    }
+}
+
+impl Display for CloneFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let known = self.known();
+        let names = known.iter_names().map(|(name, _)| name);
+        write_flags(
+            f,
+            names.chain(self.exit_signal().map(Signal::as_str)),
+            self.unknown(),
+        )
+    }
+}
+
+impl Debug for CloneFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "CloneFlags({self})")
+    }
+}
+
+/// Type of a socket combined with `SOCK_NONBLOCK` and `SOCK_CLOEXEC`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+pub struct SocketType(pub i32);
+
+impl SocketType {
+    /// Bits of the type, the rest are flags
+    const TYPE_MASK: i32 = 0xf;
+
+    #[must_use]
+    pub fn bits(self) -> i32 {
+        self.0
+    }
+
+    /// Name of the type, like `SOCK_STREAM`, `None` for unknown types
+    #[must_use]
+    pub fn type_name(self) -> Option<&'static str> {
+        let name = match self.0 & Self::TYPE_MASK {
+            libc::SOCK_STREAM => "SOCK_STREAM",
+            libc::SOCK_DGRAM => "SOCK_DGRAM",
+            libc::SOCK_RAW => "SOCK_RAW",
+            libc::SOCK_RDM => "SOCK_RDM",
+            libc::SOCK_SEQPACKET => "SOCK_SEQPACKET",
+            libc::SOCK_DCCP => "SOCK_DCCP",
+            // libc::SOCK_PACKET is deprecated
+            10 => "SOCK_PACKET",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// Flags known to this build, without the type and the unknown bits
+    #[must_use]
+    pub fn known(self) -> SockFlag {
+        SockFlag::from_bits_truncate(self.0 & !Self::TYPE_MASK)
+    }
+
+    /// Bits which aren't a known type or flags, 0 if there are none
+    #[must_use]
+    pub fn unknown(self) -> i32 {
+        let r#type = if self.type_name().is_some() {
+            Self::TYPE_MASK
+        } else {
+            0
+        };
+        self.0 & !(r#type | SockFlag::all().bits())
+    }
+}
+
+impl Display for SocketType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let known = self.known();
+        let names = known.iter_names().map(|(name, _)| name);
+        write_flags(f, self.type_name().into_iter().chain(names), self.unknown())
+    }
+}
+
+impl Debug for SocketType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SocketType({self})")
+    }
+}
@@ -0,0 +1,103 @@
+//! Hooks called while tracing, so library users can act on the tracee at its stops
+//!
+//! [`SyscallIter`](crate::syscall::SyscallIter) calls a [`TraceHandler`] from
+//! [`SyscallIter::next_with`](crate::syscall::SyscallIter::next_with) or
+//! [`SyscallIter::run`](crate::syscall::SyscallIter::run), plain iteration uses `()`, which lets
+//! the tracee continue everywhere.
+use nix::{sys::signal::Signal, unistd::Pid};
+
+use crate::{
+    syscall::{Syscall, parse_error::TraceEvent},
+    tracee::Tracee,
+};
+
+/// What the tracer does with the tracee after a hook returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Action {
+    /// Let the tracee go on as it would without the handler
+    #[default]
+    Continue,
+    /// At syscall entry, don't run the syscall, it returns the value instead, e.g. `-EPERM` to
+    /// deny it, it's reported as [`TraceEvent::SyscallSkipped`]
+    ///
+    /// For signals, the signal isn't delivered to the tracee. Elsewhere the same as
+    /// [`Action::Continue`].
+    Skip(i64),
+    /// At syscall entry, run the syscall with these arguments instead, the record contains
+    /// them, elsewhere the same as [`Action::Continue`]
+    SetArgs([u64; 6]),
+    /// At syscall exit, the syscall returns this value instead, it's reported as
+    /// [`TraceEvent::Tampered`] and the record keeps the original result, like for
+    /// [`Tamper`](crate::syscall::inject::Tamper) rules, elsewhere the same as
+    /// [`Action::Continue`]
+    SetReturnValue(i64),
+    /// Stop tracing, the tracee continues untraced and [`TraceEvent::Detached`] ends the trace
+    ///
+    /// With seccomp, syscalls stopped on by the filter fail with `ENOSYS` after detaching, and
+    /// only the main tracee is detached.
+    Detach,
+}
+
+/// Syscall the tracee is stopped on at its entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallEntry {
+    pub number: u64,
+    pub args: [u64; 6],
+    pub instruction_pointer: u64,
+}
+
+/// Hooks called at stops of the tracee, all of them let the tracee continue by default
+///
+/// Only syscalls matching the [`SyscallFilter`](crate::syscall::filter::SyscallFilter) are
+/// passed to the syscall hooks.
+pub trait TraceHandler {
+    /// Called before the kernel runs the syscall, the arguments can still be inspected
+    fn on_syscall_enter(&mut self, tracee: &mut Tracee, entry: &SyscallEntry) -> Action {
+        let _ = (tracee, entry);
+        Action::Continue
+    }
+
+    /// Called with the record of a returned syscall, or with [`TraceEvent::SyscallError`] if it
+    /// failed, the tracee is still stopped at the syscall exit
+    fn on_syscall_exit(
+        &mut self,
+        tracee: &mut Tracee,
+        syscall: Result<&Syscall, &TraceEvent>,
+    ) -> Action {
+        let _ = (tracee, syscall);
+        Action::Continue
+    }
+
+    /// Called when a signal is about to be delivered to the tracee
+    ///
+    /// Only signals received between syscalls are passed to it. Signals received while a syscall
+    /// runs, e.g. a `SIGALRM` interrupting a blocking `read`, are delivered to the tracee without
+    /// calling it.
+    fn on_signal(&mut self, tracee: &mut Tracee, signal: Signal) -> Action {
+        let _ = (tracee, signal);
+        Action::Continue
+    }
+
+    /// Called after the tracee executed a new program
+    fn on_exec(&mut self, tracee: &mut Tracee, executable: &[u8]) -> Action {
+        let _ = (tracee, executable);
+        Action::Continue
+    }
+
+    /// Called when the tracee created a new process or thread `child`, only reported with
+    /// seccomp, which traces children too
+    fn on_fork(&mut self, tracee: &mut Tracee, child: Pid) -> Action {
+        let _ = (tracee, child);
+        Action::Continue
+    }
+
+    /// Called with [`TraceEvent::ProcessExit`] or [`TraceEvent::Terminated`], the tracee can't
+    /// be used anymore
+    fn on_exit(&mut self, tracee: &mut Tracee, event: &TraceEvent) {
+        let _ = (tracee, event);
+    }
+}
+
+/// Continues everywhere, used by the [`Iterator`] implementation of
+/// [`SyscallIter`](crate::syscall::SyscallIter)
+impl TraceHandler for () {}
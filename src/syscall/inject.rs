@@ -0,0 +1,783 @@
+//! Changing behavior of the tracee's syscalls, for resilience testing
+//!
+//! Rules select syscalls with a [`SyscallMatcher`] and are checked at syscall entry, in the
+//! order they were added. The first matching rule of each kind is applied, so a syscall can be
+//! both delayed and faulted.
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    os::unix::ffi::OsStrExt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::debug;
+use nix::errno::Errno;
+use thiserror::Error;
+
+use crate::{
+    redact::glob_matches,
+    syscall::{
+        SyscallInfo,
+        filter::{FilterParseError, SyscallFilter},
+        parse_error::{TraceErrEvt, TraceError, TraceEvent},
+        replay::{Replay, Replayed},
+        table::{self, ArgType},
+    },
+    tracee::Tracee,
+};
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum RuleParseError {
+    #[error(transparent)]
+    Filter(#[from] FilterParseError),
+    #[error("unknown errno '{0}'")]
+    UnknownErrno(String),
+    #[error("invalid option '{0}'")]
+    InvalidOption(String),
+    #[error("rule is missing the required option '{0}='")]
+    MissingOption(&'static str),
+    #[error("rule needs exactly one of the options 'return=', 'truncate=' or 'content='")]
+    MissingAction,
+    #[error("could not read fixture file '{0}'")]
+    Fixture(String),
+}
+
+/// Selects syscalls by their number and arguments, optionally only some of their occurrences
+#[derive(Debug, Clone)]
+pub struct SyscallMatcher {
+    filter: SyscallFilter,
+    path: Option<Vec<u8>>,
+    fd: Option<u64>,
+    fd_target: Option<Vec<u8>>,
+    nth: Option<usize>,
+    probability: Option<f64>,
+    /// Seed set by [`SyscallMatcher::seed`], `None` if the current time was used
+    seed: Option<u64>,
+    /// Number of syscalls matched so far, before `nth` and `probability` are applied
+    occurrences: usize,
+    /// State of the xorshift generator used for `probability`
+    random: u64,
+}
+
+impl SyscallMatcher {
+    #[must_use]
+    pub fn new(filter: SyscallFilter) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos());
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "only the low bits are random"
+        )]
+        Self {
+            filter,
+            path: None,
+            fd: None,
+            fd_target: None,
+            nth: None,
+            probability: None,
+            seed: None,
+            occurrences: 0,
+            random: seed as u64 | 1,
+        }
+    }
+
+    /// Only syscalls with a path argument matching a glob `pattern`, like `/etc/*`
+    #[must_use]
+    pub fn path(mut self, pattern: impl Into<Vec<u8>>) -> Self {
+        self.path = Some(pattern.into());
+        self
+    }
+
+    /// Only syscalls with `fd` as one of their file descriptor arguments
+    #[must_use]
+    pub fn fd(mut self, fd: u64) -> Self {
+        self.fd = Some(fd);
+        self
+    }
+
+    /// Only syscalls with a file descriptor argument whose target in `/proc/<pid>/fd/` matches a
+    /// glob `pattern`, like `socket:*` or `/tmp/*`
+    #[must_use]
+    pub fn fd_target(mut self, pattern: impl Into<Vec<u8>>) -> Self {
+        self.fd_target = Some(pattern.into());
+        self
+    }
+
+    /// Only the `n`th matching syscall, counting from 1
+    #[must_use]
+    pub fn nth(mut self, n: usize) -> Self {
+        self.nth = Some(n);
+        self
+    }
+
+    /// Only match each syscall with `probability` between 0 and 1
+    #[must_use]
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.probability = Some(probability);
+        self
+    }
+
+    /// Seed of the generator used for [`SyscallMatcher::probability`], to make traces
+    /// reproducible, by default the current time is used
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self.random = seed | 1;
+        self
+    }
+
+    /// Checks the syscall the tracee is stopped on at its entry, every call counts as an
+    /// occurrence for [`SyscallMatcher::nth`]
+    pub fn matches(&mut self, tracee: &Tracee, number: u64, args: &[u64; 6]) -> bool {
+        if !self.filter.matches(number) {
+            return false;
+        }
+        let arg_types = table::lookup(number).map_or(&[][..], |signature| signature.args);
+        if let Some(pattern) = &self.path {
+            let matched = arg_types
+                .iter()
+                .zip(args)
+                .filter(|(arg_type, _)| **arg_type == ArgType::Path)
+                .filter_map(|(_, pointer)| tracee.strcpy(*pointer).ok())
+                .any(|path| glob_matches(pattern, path.strip_suffix(b"\0").unwrap_or(&path)));
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(fd) = self.fd {
+            let matched = arg_types
+                .iter()
+                .zip(args)
+                .any(|(arg_type, arg)| *arg_type == ArgType::Fd && *arg == fd);
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.fd_target {
+            let matched = arg_types
+                .iter()
+                .zip(args)
+                .filter(|(arg_type, _)| **arg_type == ArgType::Fd)
+                .filter_map(|(_, fd)| tracee.fd_target(*fd).ok())
+                .any(|target| glob_matches(pattern, target.as_os_str().as_bytes()));
+            if !matched {
+                return false;
+            }
+        }
+        self.occurrences += 1;
+        if self.nth.is_some_and(|nth| nth != self.occurrences) {
+            return false;
+        }
+        self.probability
+            .is_none_or(|probability| self.next_random() < probability)
+    }
+
+    /// Uniformly distributed number in 0..1
+    #[expect(clippy::cast_precision_loss, reason = "53 bits fit into the mantissa")]
+    fn next_random(&mut self) -> f64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        (self.random >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Applies a `key=value` option of a rule, returns `Ok(false)` if the key isn't known
+    fn parse_option(&mut self, key: &str, value: &str) -> Result<bool, RuleParseError> {
+        let invalid = || RuleParseError::InvalidOption(format!("{key}={value}"));
+        match key {
+            "path" => self.path = Some(value.into()),
+            // a number is the descriptor itself, anything else a pattern of its target
+            "fd" => match value.parse() {
+                Ok(fd) => self.fd = Some(fd),
+                Err(_) => self.fd_target = Some(value.into()),
+            },
+            "when" => self.nth = Some(value.parse().map_err(|_| invalid())?),
+            "probability" => {
+                let probability = value.parse::<f64>().map_err(|_| invalid())?;
+                if !(0.0..=1.0).contains(&probability) {
+                    return Err(invalid());
+                }
+                self.probability = Some(probability);
+            }
+            "seed" => {
+                let seed = value.parse::<u64>().map_err(|_| invalid())?;
+                self.seed = Some(seed);
+                self.random = seed | 1;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// Formats the matcher as the first part of a rule, like `openat:path=/etc/*:when=3`
+impl Display for SyscallMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.filter)?;
+        if let Some(path) = &self.path {
+            write!(f, ":path={}", path.escape_ascii())?;
+        }
+        if let Some(fd) = self.fd {
+            write!(f, ":fd={fd}")?;
+        }
+        if let Some(target) = &self.fd_target {
+            write!(f, ":fd={}", target.escape_ascii())?;
+        }
+        if let Some(nth) = self.nth {
+            write!(f, ":when={nth}")?;
+        }
+        if let Some(probability) = self.probability {
+            write!(f, ":probability={probability}")?;
+        }
+        if let Some(seed) = self.seed {
+            write!(f, ":seed={seed}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes matching syscalls fail with `error` without running them
+#[derive(Debug, Clone)]
+pub struct Fault {
+    pub matcher: SyscallMatcher,
+    pub error: Errno,
+}
+
+/// Parses rules like `openat:error=ENOENT:path=/etc/config:when=3`
+///
+/// The first part is a [`SyscallFilter`], followed by `:` separated options. `path=`, `fd=`,
+/// `when=`, `probability=` and `seed=` configure the [`SyscallMatcher`], `error=` is required.
+impl FromStr for Fault {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut matcher, options) = parse_rule(s)?;
+        let mut error = None;
+        for (key, value) in options {
+            if matcher.parse_option(key, value)? {
+                continue;
+            }
+            match key {
+                "error" => error = Some(parse_errno(value)?),
+                _ => return Err(RuleParseError::InvalidOption(format!("{key}={value}"))),
+            }
+        }
+        Ok(Self {
+            matcher,
+            error: error.ok_or(RuleParseError::MissingOption("error"))?,
+        })
+    }
+}
+
+/// Formats the fault as a rule which parses back to it
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:error={:?}", self.matcher, self.error)
+    }
+}
+
+/// How a [`Tamper`] rule changes the result of a successful syscall
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TamperAction {
+    /// Replace the return value, like a fake pid
+    Return(i64),
+    /// Return at most this many bytes, the rest of the buffer is left as the kernel wrote it
+    Truncate(u64),
+    /// Fill the output buffer with these bytes instead, successive calls on the same file
+    /// descriptor get the following bytes until the end is reached
+    ///
+    /// Supported for `read`, `pread64`, `recvfrom`, `getrandom` and `readlink`.
+    Content(Vec<u8>),
+}
+
+/// Rewrites the result of matching syscalls after they ran
+#[derive(Debug, Clone)]
+pub struct Tamper {
+    pub matcher: SyscallMatcher,
+    pub action: TamperAction,
+    /// Bytes of [`TamperAction::Content`] already returned, keyed by file descriptor
+    served: HashMap<u64, usize>,
+}
+
+impl Tamper {
+    #[must_use]
+    pub fn new(matcher: SyscallMatcher, action: TamperAction) -> Self {
+        Self {
+            matcher,
+            action,
+            served: HashMap::new(),
+        }
+    }
+
+    /// Resolves the action for the syscall the tracee is stopped on at its entry
+    fn tampering(&mut self, number: u64, args: &[u64; 6]) -> Option<Tampering> {
+        let action = match &self.action {
+            TamperAction::Return(value) => TamperingAction::Return(*value),
+            TamperAction::Truncate(count) => TamperingAction::Truncate(*count),
+            TamperAction::Content(content) => {
+                let (fd, address, len) = output_buffer(number, args)?;
+                let served = self.served.entry(fd).or_default();
+                let start = (*served).min(content.len());
+                let end = start.saturating_add(len.try_into().unwrap_or(usize::MAX));
+                let data = content[start..end.min(content.len())].to_vec();
+                *served += data.len();
+                TamperingAction::Write { address, data }
+            }
+        };
+        Some(Tampering { id: number, action })
+    }
+}
+
+/// Parses rules like `getpid:return=42`, `read:truncate=1:fd=3` or
+/// `read:fd=/etc/resolv.conf:content=@fixture.conf`
+///
+/// The first part is a [`SyscallFilter`], followed by `:` separated options. Exactly one of
+/// `return=`, `truncate=` or `content=` is required, content starting with `@` is read from the
+/// file at the rest of the value. The other options configure the [`SyscallMatcher`] like for a
+/// [`Fault`].
+impl FromStr for Tamper {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut matcher, options) = parse_rule(s)?;
+        let mut action = None;
+        for (key, value) in options {
+            if matcher.parse_option(key, value)? {
+                continue;
+            }
+            let invalid = || RuleParseError::InvalidOption(format!("{key}={value}"));
+            let parsed = match key {
+                "return" => TamperAction::Return(value.parse().map_err(|_| invalid())?),
+                "truncate" => TamperAction::Truncate(value.parse().map_err(|_| invalid())?),
+                "content" => TamperAction::Content(match value.strip_prefix('@') {
+                    Some(path) => {
+                        std::fs::read(path).map_err(|_| RuleParseError::Fixture(path.to_owned()))?
+                    }
+                    None => value.as_bytes().to_vec(),
+                }),
+                _ => return Err(invalid()),
+            };
+            if action.replace(parsed).is_some() {
+                return Err(invalid());
+            }
+        }
+        Ok(Self::new(
+            matcher,
+            action.ok_or(RuleParseError::MissingAction)?,
+        ))
+    }
+}
+
+/// Formats the tamper as a rule, content is escaped and not read back from a fixture file
+impl Display for Tamper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.matcher)?;
+        match &self.action {
+            TamperAction::Return(value) => write!(f, "return={value}"),
+            TamperAction::Truncate(count) => write!(f, "truncate={count}"),
+            TamperAction::Content(content) => write!(f, "content={}", content.escape_ascii()),
+        }
+    }
+}
+
+/// File descriptor, address and length of the buffer filled by a syscall
+fn output_buffer(number: u64, args: &[u64; 6]) -> Option<(u64, u64, u64)> {
+    match number.cast_signed() {
+        libc::SYS_read | libc::SYS_pread64 | libc::SYS_recvfrom => {
+            Some((args[0], args[1], args[2]))
+        }
+        // there's no file descriptor, the content is shared by all calls
+        libc::SYS_getrandom => Some((u64::MAX, args[0], args[1])),
+        libc::SYS_readlink => Some((u64::MAX, args[1], args[2])),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TamperingAction {
+    Return(i64),
+    Truncate(u64),
+    Write { address: u64, data: Vec<u8> },
+}
+
+/// [`Tamper`] rule resolved for a single syscall, applied at its exit
+#[derive(Debug, Clone)]
+pub struct Tampering {
+    /// Syscall number
+    id: u64,
+    action: TamperingAction,
+}
+
+impl Tampering {
+    /// Changes the result of the syscall the tracee is stopped on at its exit, the syscall
+    /// returned `return_value`
+    ///
+    /// Returns the new return value and the event recording the change.
+    pub(crate) fn apply(
+        self,
+        tracee: &Tracee,
+        return_value: i64,
+    ) -> Result<(i64, TraceEvent), Errno> {
+        let mut written = 0;
+        let tampered = match self.action {
+            TamperingAction::Return(value) => value,
+            TamperingAction::Truncate(count) => {
+                return_value.min(count.try_into().unwrap_or(i64::MAX))
+            }
+            TamperingAction::Write { address, data } => {
+                written = tracee.memwrite(address, &data)?;
+                written.try_into().unwrap_or(i64::MAX)
+            }
+        };
+        if tampered != return_value {
+            tracee.set_return_value(tampered)?;
+        }
+        let event = TraceEvent::Tampered {
+            id: self.id,
+            original: return_value,
+            tampered,
+            written,
+        };
+        Ok((tampered, event))
+    }
+}
+
+/// Stop of a syscall at which the tracee is held by a [`Delay`]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+#[repr(u8)]
+pub enum DelayPoint {
+    /// Before the syscall runs
+    #[default]
+    Entry = 0,
+    /// After the syscall returned, before the tracee sees the result
+    Exit = 1,
+}
+
+/// Keeps the tracee stopped for `duration` at a stop of matching syscalls
+#[derive(Debug, Clone)]
+pub struct Delay {
+    pub matcher: SyscallMatcher,
+    pub duration: Duration,
+    pub at: DelayPoint,
+}
+
+/// Parses rules like `recvfrom:delay=200ms:fd=3:at=exit`
+///
+/// The first part is a [`SyscallFilter`], followed by `:` separated options. `delay=` is
+/// required and takes a number with one of the units `ns`, `us`, `ms` or `s`. `at=` is `entry`
+/// (default) or `exit`. The other options configure the [`SyscallMatcher`] like for a [`Fault`].
+impl FromStr for Delay {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut matcher, options) = parse_rule(s)?;
+        let mut duration = None;
+        let mut at = DelayPoint::default();
+        for (key, value) in options {
+            if matcher.parse_option(key, value)? {
+                continue;
+            }
+            let invalid = || RuleParseError::InvalidOption(format!("{key}={value}"));
+            match key {
+                "delay" => duration = Some(parse_duration(value).ok_or_else(invalid)?),
+                "at" => {
+                    at = match value {
+                        "entry" => DelayPoint::Entry,
+                        "exit" => DelayPoint::Exit,
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Self {
+            matcher,
+            duration: duration.ok_or(RuleParseError::MissingOption("delay"))?,
+            at,
+        })
+    }
+}
+
+/// Formats the delay as a rule which parses back to it
+impl Display for Delay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:delay=", self.matcher)?;
+        let nanos = self.duration.as_nanos();
+        match [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")]
+            .into_iter()
+            .find(|(unit, _)| nanos.is_multiple_of(*unit))
+        {
+            Some((unit, name)) => write!(f, "{}{name}", nanos / unit)?,
+            None => write!(f, "{nanos}ns")?,
+        }
+        if self.at == DelayPoint::Exit {
+            f.write_str(":at=exit")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a duration like `200ms`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let amount = value[..split].parse().ok()?;
+    match &value[split..] {
+        "ns" => Some(Duration::from_nanos(amount)),
+        "us" => Some(Duration::from_micros(amount)),
+        "ms" => Some(Duration::from_millis(amount)),
+        "s" => Some(Duration::from_secs(amount)),
+        _ => None,
+    }
+}
+
+/// `key=value` options of a rule
+type RuleOptions<'a> = Vec<(&'a str, &'a str)>;
+
+/// Splits a rule into its matcher and options
+///
+/// A part without `=` belongs to the value of the previous option, so values like `socket:*`
+/// don't have to be escaped.
+fn parse_rule(s: &str) -> Result<(SyscallMatcher, RuleOptions<'_>), RuleParseError> {
+    let (filter, rest) = s.split_once(':').unwrap_or((s, ""));
+    let mut options: RuleOptions<'_> = vec![];
+    let mut offset = filter.len() + 1;
+    for part in rest.split(':').filter(|_| !rest.is_empty()) {
+        let end = offset + part.len();
+        match (part.split_once('='), options.last_mut()) {
+            (Some((key, _)), _) => options.push((key, &s[offset + key.len() + 1..end])),
+            (None, Some((_, value))) => {
+                let start = end - part.len() - 1 - value.len();
+                *value = &s[start..end];
+            }
+            (None, None) => return Err(RuleParseError::InvalidOption(part.to_owned())),
+        }
+        offset = end + 1;
+    }
+    Ok((SyscallMatcher::new(filter.parse()?), options))
+}
+
+/// Parses an errno by its name, like `ENOENT`, or its number
+fn parse_errno(name: &str) -> Result<Errno, RuleParseError> {
+    if let Ok(number) = name.parse() {
+        return Ok(Errno::from_raw(number));
+    }
+    (1..256)
+        .map(Errno::from_raw)
+        .find(|errno| *errno != Errno::UnknownErrno && format!("{errno:?}") == name)
+        .ok_or_else(|| RuleParseError::UnknownErrno(name.to_owned()))
+}
+
+/// Rules applied to the tracee's syscalls, see
+/// [`SyscallIterOpts::inject_fault`](crate::syscall::SyscallIterOpts::inject_fault),
+/// [`SyscallIterOpts::inject_delay`](crate::syscall::SyscallIterOpts::inject_delay) and
+/// [`SyscallIterOpts::tamper`](crate::syscall::SyscallIterOpts::tamper)
+#[derive(Debug, Clone, Default)]
+pub struct Injector {
+    faults: Vec<Fault>,
+    delays: Vec<Delay>,
+    tampers: Vec<Tamper>,
+    /// Ended when the tracee diverges from the recording
+    replay: Option<Replay>,
+    /// [`TraceEvent::DelayInjected`], [`TraceEvent::Tampered`] and
+    /// [`TraceEvent::ReplayDiverged`] events not yet returned
+    events: Vec<TraceEvent>,
+}
+
+impl Injector {
+    pub fn add_fault(&mut self, fault: Fault) {
+        self.faults.push(fault);
+    }
+
+    pub fn add_delay(&mut self, delay: Delay) {
+        self.delays.push(delay);
+    }
+
+    pub fn add_tamper(&mut self, tamper: Tamper) {
+        self.tampers.push(tamper);
+    }
+
+    pub fn set_replay(&mut self, replay: Replay) {
+        self.replay = Some(replay);
+    }
+
+    #[must_use]
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    #[must_use]
+    pub fn delays(&self) -> &[Delay] {
+        &self.delays
+    }
+
+    #[must_use]
+    pub fn tampers(&self) -> &[Tamper] {
+        &self.tampers
+    }
+
+    /// Recording fed back to the tracee, `None` once the tracee diverged from it
+    #[must_use]
+    pub fn recording(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.faults.is_empty()
+            && self.delays.is_empty()
+            && self.tampers.is_empty()
+            && self.replay.is_none()
+    }
+
+    /// Tampering of the result of the syscall the tracee is stopped on at its entry, see
+    /// [`Tracee::set_tampering`]
+    pub fn tampering(
+        &mut self,
+        tracee: &Tracee,
+        number: u64,
+        args: &[u64; 6],
+    ) -> Option<Tampering> {
+        self.tampers.iter_mut().find_map(|tamper| {
+            if tamper.matcher.matches(tracee, number, args) {
+                tamper.tampering(number, args)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Records an event which is returned before the record of the current syscall
+    pub fn push_event(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    /// Applies the rules to the syscall the tracee is stopped on at its entry
+    ///
+    /// Faulted syscalls are returned as [`TraceEvent::FaultInjected`], they don't need
+    /// [`Injector::exit_syscall`]. Otherwise returns the delay to hold the tracee for at the
+    /// syscall exit.
+    pub fn enter_syscall(
+        &mut self,
+        tracee: &mut Tracee,
+        number: u64,
+        args: &[u64; 6],
+    ) -> Result<Option<Duration>, TraceErrEvt> {
+        let exit_delay = match self.delay(tracee, number, args) {
+            Some((delay, DelayPoint::Entry)) => {
+                self.hold(tracee, number, delay, DelayPoint::Entry)?;
+                None
+            }
+            Some((delay, DelayPoint::Exit)) => Some(delay),
+            None => None,
+        };
+        if let Some(error) = self.fault(tracee, number, args) {
+            debug!("injecting {error:?} into syscall {number}");
+            tracee.begin_syscall();
+            tracee.inject_error(error)?;
+            let time = tracee.syscall_time();
+            if let Some(delay) = exit_delay {
+                self.hold(tracee, number, delay, DelayPoint::Exit)?;
+            }
+            return Err(TraceErrEvt::Event(TraceEvent::FaultInjected {
+                id: number,
+                error,
+                time,
+            }));
+        }
+        let tampering = self.tampering(tracee, number, args);
+        tracee.set_tampering(tampering);
+        Ok(exit_delay)
+    }
+
+    /// Feeds back the recorded result of the syscall the tracee is stopped on at its entry, see
+    /// [`Replay::replay`]
+    ///
+    /// Returns `Ok(None)` if the syscall has to be run by the kernel, a divergence is recorded
+    /// as an event and ends the replay.
+    pub fn replay(
+        &mut self,
+        tracee: &mut Tracee,
+        number: u64,
+        args: &[u64; 6],
+    ) -> Result<Option<SyscallInfo>, TraceErrEvt> {
+        let Some(replay) = &mut self.replay else {
+            return Ok(None);
+        };
+        match replay.replay(tracee, number, args)? {
+            Replayed::Run => Ok(None),
+            Replayed::Syscall(syscall) => Ok(Some(syscall)),
+            Replayed::Error(event) => Err(TraceErrEvt::Event(event)),
+            Replayed::Diverged(event) => {
+                debug!("{event}");
+                self.replay = None;
+                self.push_event(event);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Finishes the rules of syscall `id` at its exit stop, after its result was read
+    pub fn exit_syscall(
+        &mut self,
+        tracee: &mut Tracee,
+        id: u64,
+        delay: Option<Duration>,
+    ) -> Result<(), TraceError> {
+        if let Some(event) = tracee.take_tampered() {
+            self.push_event(event);
+        }
+        if let Some(delay) = delay {
+            self.hold(tracee, id, delay, DelayPoint::Exit)?;
+        }
+        Ok(())
+    }
+
+    /// Delay of the syscall the tracee is stopped on at its entry
+    pub fn delay(
+        &mut self,
+        tracee: &Tracee,
+        number: u64,
+        args: &[u64; 6],
+    ) -> Option<(Duration, DelayPoint)> {
+        self.delays.iter_mut().find_map(|delay| {
+            delay
+                .matcher
+                .matches(tracee, number, args)
+                .then_some((delay.duration, delay.at))
+        })
+    }
+
+    /// Holds the tracee at the current stop of syscall `id` and records it as an event
+    pub fn hold(
+        &mut self,
+        tracee: &mut Tracee,
+        id: u64,
+        delay: Duration,
+        at: DelayPoint,
+    ) -> Result<(), TraceError> {
+        tracee.hold(delay)?;
+        self.events
+            .push(TraceEvent::DelayInjected { id, delay, at });
+        Ok(())
+    }
+
+    /// Events of the delays held and results tampered since the last call
+    pub fn take_events(&mut self) -> Vec<TraceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Error which should be injected into the syscall the tracee is stopped on at its entry
+    pub fn fault(&mut self, tracee: &Tracee, number: u64, args: &[u64; 6]) -> Option<Errno> {
+        self.faults.iter_mut().find_map(|fault| {
+            fault
+                .matcher
+                .matches(tracee, number, args)
+                .then_some(fault.error)
+        })
+    }
+}
@@ -4,15 +4,11 @@ use std::fmt::Debug;
 use libc::sockaddr;
 use nix::{
     errno::Errno,
-    fcntl::OFlag,
-    sys::{
-        signal::Signal, socket::{self, AddressFamily}, stat::Mode
-    },
+    sys::{signal::Signal, socket::AddressFamily},
 };
 use rkyv::{
-    Archive, Archived, Deserialize, Resolver, Serialize,
+    Archive, Archived, Resolver, Serialize,
     rancor::{self, Fallible},
-    rend,
     with::{ArchiveWith, DeserializeWith, SerializeWith},
 };
 
@@ -22,8 +18,6 @@ pub enum SyscallNewTypeError {
     CouldNotParseEnum(i32),
     #[error("invalid flags value: {0:#X}")]
     InvalidFlagI32(i32),
-    #[error("invalid flags value: {0:#X}")]
-    InvalidFlagU32(u32),
     #[error("invalid conversion: {0:?}")]
     InvalidConversion(#[from] nix::Error),
     #[error(transparent)]
@@ -72,89 +66,8 @@ impl From<sockaddr_ser> for sockaddr {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SocketType {
-    pub r#type: socket::SockType,
-    pub flags: socket::SockFlag,
-}
-
-impl Archive for SocketType {
-    type Archived = Archived<i32>;
-
-    type Resolver = Resolver<i32>;
-
-    fn resolve(&self, (): (), out: rkyv::Place<Self::Archived>) {
-        // log::info!("help: {:?}", resolver.type_id());
-        let num: i32 = self.into();
-        num.resolve((), out);
-    }
-}
-
-impl<S: Fallible> Serialize<S> for SocketType {
-    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        let num: i32 = self.into();
-        num.serialize(serializer)
-    }
-}
-
-impl<D: Fallible<Error = SyscallNewTypeError>> Deserialize<SocketType, D> for rend::i32_le {
-    fn deserialize(&self, deserializer: &mut D) -> Result<SocketType, <D as Fallible>::Error> {
-        let num: i32 = self.deserialize(deserializer)?;
-        Ok(SocketType::try_from(num)?)
-    }
-}
-
-impl TryFrom<i32> for SocketType {
-    type Error = nix::Error;
-
-    fn try_from(val: i32) -> Result<Self, Self::Error> {
-        let flags = socket::SockFlag::from_bits_truncate(val);
-        let sock_type = socket::SockType::try_from(val & !socket::SockFlag::all().bits())?;
-
-        Ok(SocketType {
-            r#type: sock_type,
-            flags,
-        })
-    }
-}
-
-impl From<&SocketType> for i32 {
-    fn from(value: &SocketType) -> Self {
-        let flags = value.flags.bits();
-        let r#type: i32 = value.r#type as i32;
-        flags | r#type
-    }
-}
-
 #[derive(Debug, Clone)]
-pub(super) struct NewTypeSer;
-
-// Mode
-impl ArchiveWith<Mode> for NewTypeSer {
-    type Archived = Archived<u32>;
-
-    type Resolver = Resolver<u32>;
-
-    fn resolve_with(field: &Mode, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
-        field.bits().resolve(resolver, out);
-    }
-}
-
-impl<S: Fallible> SerializeWith<Mode, S> for NewTypeSer {
-    fn serialize_with(
-        field: &Mode,
-        serializer: &mut S,
-    ) -> Result<Self::Resolver, <S as Fallible>::Error> {
-        field.bits().serialize(serializer)
-    }
-}
-
-impl<D: Fallible<Error = SyscallNewTypeError>> DeserializeWith<Archived<u32>, Mode, D> for NewTypeSer {
-    fn deserialize_with(field: &Archived<u32>, _: &mut D) -> Result<Mode, <D as Fallible>::Error> {
-        let num = field.to_native();
-        Mode::from_bits(num).ok_or(SyscallNewTypeError::InvalidFlagU32(num))
-    }
-}
+pub(crate) struct NewTypeSer;
 
 // AddressFamily
 impl ArchiveWith<AddressFamily> for NewTypeSer {
@@ -189,30 +102,6 @@ impl<D: Fallible<Error = SyscallNewTypeError>> DeserializeWith<Archived<i32>, Ad
     }
 }
 
-// OFlag
-impl ArchiveWith<OFlag> for NewTypeSer {
-    type Archived = Archived<i32>;
-
-    type Resolver = Resolver<i32>;
-
-    fn resolve_with(field: &OFlag, (): (), out: rkyv::Place<Self::Archived>) {
-        field.bits().resolve((), out);
-    }
-}
-
-impl<S: Fallible + Sized> SerializeWith<OFlag, S> for NewTypeSer {
-    fn serialize_with(field: &OFlag, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        field.bits().serialize(serializer)
-    }
-}
-
-impl<D: Fallible<Error = SyscallNewTypeError>> DeserializeWith<Archived<i32>, OFlag, D> for NewTypeSer {
-    fn deserialize_with(field: &Archived<i32>, _: &mut D) -> Result<OFlag, <D as Fallible>::Error> {
-        let num = field.to_native();
-        OFlag::from_bits(field.to_native()).ok_or(SyscallNewTypeError::InvalidFlagI32(num))
-    }
-}
-
 // Errno
 impl ArchiveWith<Errno> for NewTypeSer {
     type Archived = Archived<i32>;
@@ -266,6 +155,35 @@ impl<D: Fallible<Error = SyscallNewTypeError>> DeserializeWith<Archived<i32>, Si
     }
 }
 
+// usize, archived as u64 because rkyv archives it as u32 and cuts off `usize::MAX`
+impl ArchiveWith<usize> for NewTypeSer {
+    type Archived = Archived<u64>;
+
+    type Resolver = Resolver<u64>;
+
+    fn resolve_with(field: &usize, (): (), out: rkyv::Place<Self::Archived>) {
+        (*field as u64).resolve((), out);
+    }
+}
+
+impl<S: Fallible> SerializeWith<usize, S> for NewTypeSer {
+    fn serialize_with(
+        field: &usize,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, <S as Fallible>::Error> {
+        (*field as u64).serialize(serializer)
+    }
+}
+
+impl<D: Fallible<Error = SyscallNewTypeError>> DeserializeWith<Archived<u64>, usize, D>
+    for NewTypeSer
+{
+    fn deserialize_with(field: &Archived<u64>, _: &mut D) -> Result<usize, <D as Fallible>::Error> {
+        usize::try_from(field.to_native())
+            .map_err(|err| SyscallNewTypeError::AnythingElse(err.into()))
+    }
+}
+
 // pub struct ErrnoSer;
 //
 // impl ArchieWith<Errno> for ErrnoSer {}
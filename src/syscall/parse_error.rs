@@ -1,12 +1,17 @@
+use std::{io, time::Duration};
+
 use nix::{
     errno::Errno,
     sys::{signal::Signal, wait::WaitStatus},
 };
 use thiserror::Error;
 
-use crate::{syscall::NewTypeSer, tracee::PtraceSyscallInfo};
+use crate::{
+    syscall::{NewTypeSer, inject::DelayPoint},
+    tracee::PtraceSyscallInfo,
+};
 
-use super::SyscallDisc;
+use super::{SyscallDisc, SyscallTime};
 
 #[derive(Error, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TraceError {
@@ -18,28 +23,115 @@ pub enum TraceError {
     WaitPidError(Errno),
     #[error("syscall info struct did not contain the required entry, it contained {0:?}")]
     InvalidSyscallInfo(PtraceSyscallInfo),
+    #[error("could not read procfs file of the tracee: {0}")]
+    ProcfsError(io::ErrorKind),
+    #[error("could not parse procfs file {0} of the tracee")]
+    InvalidProcfs(&'static str),
+    #[error("syscall {syscall} has an argument with unknown value {value:#x}")]
+    InvalidArgument { syscall: SyscallDisc, value: u64 },
+    #[error("executable of the tracee is not a valid ELF file")]
+    InvalidExecutable,
+    #[error("address {0:#x} of the executable is not mapped in the tracee")]
+    UnmappedAddress(u64),
+    #[error("tracee stopped or exited before reaching the breakpoint at {0:#x}")]
+    BreakpointNotReached(u64),
+    #[error(
+        "no debug register is free for the breakpoint at {0:#x} and a software breakpoint would \
+         kill the other threads of the tracee"
+    )]
+    BreakpointInThreads(u64),
 }
 
-#[derive(
-    Error, Debug, PartialEq, Clone, Copy, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
-)]
+#[derive(Error, Debug, PartialEq, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+// stored in trace files, so variants keep their tags
+#[repr(u8)]
 pub enum TraceEvent {
     #[error("tracee terminated by OS with signal {signal:?}")]
     Terminated {
         #[rkyv(with = NewTypeSer)]
         signal: Signal,
         core_dumped: bool,
-    },
+    } = 0,
     #[error("tracee process is not running and exited with status code {0}")]
-    ProcessExit(i32),
+    ProcessExit(i32) = 1,
     #[error("syscall {syscall} returned the following error: '{error:?}'")]
     SyscallError {
         syscall: SyscallDisc,
         #[rkyv(with = NewTypeSer)]
         error: Errno,
         cpu_time: f64,
+        time: SyscallTime,
         rip: u64,
-    },
+    } = 2,
+    /// Syscall wasn't run, it returned an error injected by a
+    /// [`Fault`](crate::syscall::inject::Fault) rule
+    #[error("syscall {id} failed with injected error '{error:?}'")]
+    FaultInjected {
+        /// Syscall number
+        id: u64,
+        #[rkyv(with = NewTypeSer)]
+        error: Errno,
+        time: SyscallTime,
+    } = 3,
+    /// Tracee was held at a stop of a syscall by a [`Delay`](crate::syscall::inject::Delay)
+    /// rule, emitted before the record of the syscall
+    ///
+    /// The delay isn't part of the [`SyscallTime`] of the syscall.
+    #[error("syscall {id} was delayed by {delay:?} at its {at:?}")]
+    DelayInjected {
+        /// Syscall number
+        id: u64,
+        delay: Duration,
+        at: DelayPoint,
+    } = 4,
+    /// Result of a successful syscall was changed by a [`Tamper`](crate::syscall::inject::Tamper)
+    /// rule or a result was changed by
+    /// [`Action::SetReturnValue`](crate::syscall::handler::Action::SetReturnValue) of a handler
+    ///
+    /// Results are changed after they were recorded, the record of the syscall keeps what the
+    /// kernel returned and this event, emitted before the record, what the tracee got.
+    #[error("syscall {id} returned {tampered} instead of {original}")]
+    Tampered {
+        /// Syscall number
+        id: u64,
+        /// Return value from the kernel
+        original: i64,
+        /// Return value seen by the tracee
+        tampered: i64,
+        /// Number of bytes written into the output buffer
+        written: usize,
+    } = 5,
+    /// The tracee made syscall `actual` where the trace of a
+    /// [`Replay`](crate::syscall::replay::Replay) has `expected` at `position`, emitted before
+    /// the record of the syscall
+    ///
+    /// `expected` is `None` if there are no more syscalls in the trace, `position` is then its
+    /// length. The replay ends here, the rest of the syscalls is run by the kernel.
+    #[error("replay diverged at position {position}: expected syscall {expected:?}, got {actual}")]
+    ReplayDiverged {
+        position: usize,
+        expected: Option<u64>,
+        actual: u64,
+    } = 6,
+    /// Syscall wasn't run, because a [`TraceHandler`](crate::syscall::handler::TraceHandler)
+    /// skipped it with [`Action::Skip`](crate::syscall::handler::Action::Skip)
+    #[error("syscall {id} was skipped and returned {return_value}")]
+    SyscallSkipped {
+        /// Syscall number
+        id: u64,
+        return_value: i64,
+        time: SyscallTime,
+    } = 7,
+    /// A [`TraceHandler`](crate::syscall::handler::TraceHandler) detached from the tracee, it
+    /// runs untraced and nothing follows in the trace
+    #[error("tracer detached from the tracee")]
+    Detached = 8,
+    /// Emitted after the syscall record of a successful `execve` or `execveat`
+    #[error("tracee executed a new program")]
+    Exec {
+        /// Path of the new program, as read from `/proc/<pid>/exe`
+        executable: Vec<u8>,
+    } = 9,
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -0,0 +1,193 @@
+//! Re-running a program against a recorded trace
+//!
+//! `read`, `recvfrom`, `getrandom` and `clock_gettime` are not run by the kernel during a replay,
+//! the tracee gets their recorded results instead. Other syscalls run normally. Every traced
+//! syscall is compared with the recording, the first one which differs is reported as
+//! [`TraceEvent::ReplayDiverged`] and ends the replay.
+//!
+//! The replay has to trace the same syscalls as the recording, e.g. by using the same
+//! [`SyscallFilter`](crate::syscall::filter::SyscallFilter). `clock_gettime` is usually served
+//! by the vDSO without a syscall, only real syscalls can be replayed.
+use log::{debug, warn};
+use nix::errno::Errno;
+
+use crate::{
+    syscall::{
+        SyscallDisc, SyscallInfo, TraceData,
+        parse_error::{TraceErrEvt, TraceEvent},
+        table,
+    },
+    tracee::Tracee,
+};
+
+#[derive(Debug, Clone)]
+enum Recorded {
+    Syscall(SyscallInfo),
+    Error(SyscallDisc, Errno),
+}
+
+#[derive(Debug, Clone)]
+struct RecordedCall {
+    /// Index in the recorded trace
+    position: usize,
+    number: u64,
+    result: Recorded,
+}
+
+/// Recorded syscalls fed back to the tracee, see
+/// [`SyscallIterOpts::replay`](crate::syscall::SyscallIterOpts::replay)
+#[derive(Debug, Clone)]
+pub struct Replay {
+    calls: Vec<RecordedCall>,
+    next: usize,
+    /// Number of items in the recorded trace
+    len: usize,
+}
+
+impl Replay {
+    /// Injected faults and syscalls with unknown numbers in `trace` are not compared
+    #[must_use]
+    pub fn new(trace: &[TraceData]) -> Self {
+        let calls = trace
+            .iter()
+            .enumerate()
+            .filter_map(|(position, data)| {
+                let result = match data {
+                    TraceData::Syscall(syscall) => Recorded::Syscall(syscall.syscall.clone()),
+                    TraceData::Event(TraceEvent::SyscallError { syscall, error, .. }) => {
+                        Recorded::Error(*syscall, *error)
+                    }
+                    TraceData::Event(_) => return None,
+                };
+                let number = match &result {
+                    Recorded::Syscall(syscall) => syscall.number(),
+                    Recorded::Error(syscall, _) => {
+                        table::lookup_name(&syscall.to_string()).map(|signature| signature.number)
+                    }
+                }?;
+                Some(RecordedCall {
+                    position,
+                    number,
+                    result,
+                })
+            })
+            .collect();
+        Self {
+            calls,
+            next: 0,
+            len: trace.len(),
+        }
+    }
+
+    /// Number of items in the recorded trace
+    #[must_use]
+    pub fn trace_len(&self) -> usize {
+        self.len
+    }
+
+    /// Compares the syscall the tracee is stopped on at its entry with the recording and feeds
+    /// back its recorded result if it can be replayed
+    ///
+    /// After [`Replayed::Diverged`] the replay shouldn't be used anymore.
+    pub fn replay(
+        &mut self,
+        tracee: &mut Tracee,
+        number: u64,
+        args: &[u64; 6],
+    ) -> Result<Replayed, TraceErrEvt> {
+        let Some(call) = self.calls.get(self.next) else {
+            return Ok(Replayed::Diverged(TraceEvent::ReplayDiverged {
+                position: self.len,
+                expected: None,
+                actual: number,
+            }));
+        };
+        if call.number != number {
+            return Ok(Replayed::Diverged(TraceEvent::ReplayDiverged {
+                position: call.position,
+                expected: Some(call.number),
+                actual: number,
+            }));
+        }
+        self.next += 1;
+        let result = match &call.result {
+            Recorded::Syscall(syscall) if emulate(tracee, syscall, args)? => {
+                Replayed::Syscall(syscall.clone())
+            }
+            Recorded::Error(syscall, error) if is_replayable(*syscall) => {
+                tracee.inject_error(*error)?;
+                let regs = tracee.getregs()?;
+                Replayed::Error(TraceEvent::SyscallError {
+                    syscall: *syscall,
+                    error: *error,
+                    cpu_time: tracee.get_cpu_time()?,
+                    time: tracee.syscall_time(),
+                    rip: regs.rip,
+                })
+            }
+            Recorded::Syscall(_) | Recorded::Error(..) => Replayed::Run,
+        };
+        if !matches!(result, Replayed::Run) {
+            debug!("replayed syscall {number} from position {}", call.position);
+        }
+        Ok(result)
+    }
+}
+
+/// Outcome of [`Replay::replay`]
+#[derive(Debug)]
+pub enum Replayed {
+    /// The syscall wasn't replayed, it has to be run by the kernel
+    Run,
+    /// The tracee got the recorded result of this syscall
+    Syscall(SyscallInfo),
+    /// The tracee got the recorded error, as [`TraceEvent::SyscallError`]
+    Error(TraceEvent),
+    /// The syscall differs from the recording, as [`TraceEvent::ReplayDiverged`], it has to be
+    /// run by the kernel
+    Diverged(TraceEvent),
+}
+
+fn is_replayable(syscall: SyscallDisc) -> bool {
+    matches!(
+        syscall,
+        SyscallDisc::Read
+            | SyscallDisc::Recvfrom
+            | SyscallDisc::Getrandom
+            | SyscallDisc::ClockGettime
+    )
+}
+
+/// Makes the syscall return its recorded result, returns `false` if it can't be replayed
+fn emulate(
+    tracee: &mut Tracee,
+    syscall: &SyscallInfo,
+    args: &[u64; 6],
+) -> Result<bool, TraceErrEvt> {
+    let (address, requested, data) = match syscall {
+        SyscallInfo::Read { read_bytes, .. } => (args[1], args[2], read_bytes),
+        SyscallInfo::Recvfrom { received_bytes, .. } => (args[1], args[2], received_bytes),
+        SyscallInfo::Getrandom { random_bytes, .. } => (args[0], args[1], random_bytes),
+        SyscallInfo::ClockGettime { time, .. } => {
+            let output = time.map(|time| {
+                let mut timespec = time.as_secs().to_ne_bytes().to_vec();
+                timespec.extend_from_slice(&u64::from(time.subsec_nanos()).to_ne_bytes());
+                timespec
+            });
+            let output = output.as_deref().map(|timespec| (args[1], timespec));
+            tracee.emulate_syscall(0, output)?;
+            return Ok(true);
+        }
+        _ => return Ok(false),
+    };
+    if data.truncated || data.redacted {
+        warn!("replaying incomplete data of {} bytes", data.original_len);
+    }
+    let len = data
+        .data
+        .len()
+        .min(usize::try_from(requested).unwrap_or(usize::MAX));
+    let return_value = i64::try_from(len).unwrap_or(i64::MAX);
+    tracee.emulate_syscall(return_value, Some((address, &data.data[..len])))?;
+    Ok(true)
+}
@@ -0,0 +1,163 @@
+//! Tracing from async code, enabled by the `async` feature
+//!
+//! Ptrace requests have to come from the thread which attached to the tracee, so
+//! [`TraceStream`] spawns the [`Tracer`] and traces on a dedicated thread. The traced data are
+//! sent through a bounded channel, the tracee isn't resumed while the channel is full.
+//!
+//! Dropping the stream detaches from the tracee, which then continues untraced. The tracing
+//! thread may be waiting for the tracee, e.g. in a blocking `read`, so the tracee is interrupted
+//! by a `SIGSTOP` sent to it with `tgkill`. The tracing thread suppresses that signal at its
+//! signal-delivery-stop and detaches there, the interrupted syscall is then restarted.
+use std::{
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+    thread,
+};
+
+use futures::{
+    SinkExt, Stream,
+    channel::{mpsc, oneshot},
+    executor,
+};
+use log::{debug, warn};
+use nix::{errno::Errno, sys::signal::Signal, unistd::Pid};
+
+use crate::{
+    syscall::{
+        SyscallIterError, TraceData,
+        handler::{Action, TraceHandler},
+        parse_error::TraceError,
+    },
+    tracee::Tracee,
+    tracer::{SpawnError, TracedChild, Tracer},
+};
+
+/// Syscalls and events of a child traced on a dedicated thread
+pub struct TraceStream {
+    receiver: mpsc::Receiver<Result<TraceData, TraceError>>,
+    pid: Pid,
+    state: Arc<State>,
+}
+
+/// State of the tracing shared by the stream and its thread
+#[derive(Default)]
+struct State {
+    /// The stream was dropped
+    cancelled: AtomicBool,
+    /// The child exited and was reaped, its pid may belong to another process
+    exited: AtomicBool,
+}
+
+impl TraceStream {
+    /// Spawns the child of `tracer` on a new thread and traces it there
+    ///
+    /// At most `capacity` items are buffered. The child mustn't be waited for by other threads,
+    /// they would take its stops from the tracing thread. Once the trace ends or the child is
+    /// detached, the tracing thread waits for it to exit.
+    pub async fn spawn(capacity: usize, tracer: Tracer) -> Result<Self, SpawnError> {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let (started, start_result) = oneshot::channel();
+        let state = Arc::new(State::default());
+        let cancel = Cancel(Arc::clone(&state));
+        thread::Builder::new()
+            .name("boubo-trace".to_owned())
+            .spawn(move || match tracer.spawn() {
+                Ok(child) => {
+                    let _ = started.send(Ok(Pid::from_raw(child.id().cast_signed())));
+                    forward(child, sender, cancel);
+                }
+                Err(err) => {
+                    let _ = started.send(Err(err));
+                }
+            })
+            .map_err(|_| SyscallIterError::ThreadError)?;
+        match start_result.await {
+            Ok(result) => result.map(|pid| Self {
+                receiver,
+                pid,
+                state,
+            }),
+            // spawning the child panicked
+            Err(oneshot::Canceled) => Err(SyscallIterError::ThreadError.into()),
+        }
+    }
+
+    /// Process id of the traced child
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.pid.as_raw().cast_unsigned()
+    }
+}
+
+impl Stream for TraceStream {
+    type Item = Result<TraceData, TraceError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for TraceStream {
+    fn drop(&mut self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        if self.state.exited.load(Ordering::SeqCst) {
+            return;
+        }
+        let pid = self.pid.as_raw();
+        // SAFETY: tgkill only takes integers
+        if unsafe { libc::tgkill(pid, pid, Signal::SIGSTOP as i32) } == -1 {
+            let err = Errno::last();
+            if err != Errno::ESRCH {
+                warn!("could not interrupt the tracee: {err}");
+            }
+        }
+    }
+}
+
+/// Detaches at the signal-delivery-stop of the `SIGSTOP` sent when the stream is dropped
+struct Cancel(Arc<State>);
+
+impl Cancel {
+    fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl TraceHandler for Cancel {
+    fn on_signal(&mut self, tracee: &mut Tracee, signal: Signal) -> Action {
+        if signal == Signal::SIGSTOP && self.is_cancelled() {
+            debug!("trace stream was dropped, detaching");
+            tracee.suppress_signal();
+            Action::Detach
+        } else {
+            Action::Continue
+        }
+    }
+}
+
+/// Sends the traced data until the trace ends or the stream is dropped, then waits for the child
+///
+/// The data traced after the stream was dropped are discarded until the tracee reaches the
+/// signal-delivery-stop of the interrupting `SIGSTOP`.
+fn forward(
+    mut child: TracedChild,
+    mut sender: mpsc::Sender<Result<TraceData, TraceError>>,
+    mut cancel: Cancel,
+) {
+    while let Some(data) = child.next_with(&mut cancel) {
+        if child.exit_status().is_some() {
+            cancel.0.exited.store(true, Ordering::SeqCst);
+        }
+        if !cancel.is_cancelled() && executor::block_on(sender.send(data)).is_err() {
+            // the stream was dropped between two checks of the flag, its SIGSTOP is on the way
+            debug!("trace stream was dropped");
+        }
+    }
+    if let Err(err) = child.wait() {
+        warn!("could not wait for the child: {err}");
+    }
+}
@@ -0,0 +1,741 @@
+//! Names and argument signatures of all x86-64 syscalls
+use super::capture::CapturedBytes;
+use crate::tracee::Tracee;
+
+/// Kind of a syscall argument, used for best-effort decoding of syscalls without a dedicated
+/// [`SyscallInfo`](super::SyscallInfo) variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    Int,
+    Fd,
+    Pointer,
+    /// NUL-terminated path read by the kernel
+    Path,
+    /// NUL-terminated string read by the kernel, which isn't a path
+    String,
+    Flags,
+}
+
+/// Argument of a syscall decoded according to its [`ArgType`]
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[rkyv(derive(Debug))]
+#[repr(u8)]
+pub enum ArgValue {
+    Int(i64) = 0,
+    Fd(i32) = 1,
+    Pointer(u64) = 2,
+    Path(CapturedBytes) = 3,
+    String(CapturedBytes) = 4,
+    Flags(u64) = 5,
+}
+
+impl ArgType {
+    /// Decodes raw argument value, strings are read from tracee's memory
+    ///
+    /// If a string cannot be read, it's kept as a pointer.
+    #[must_use]
+    pub fn decode(self, tracee: &mut Tracee, raw: u64) -> ArgValue {
+        match self {
+            ArgType::Int => ArgValue::Int(raw.cast_signed()),
+            ArgType::Fd => ArgValue::Fd(raw as i32),
+            ArgType::Pointer => ArgValue::Pointer(raw),
+            ArgType::Path | ArgType::String if raw == 0 => ArgValue::Pointer(raw),
+            ArgType::Path => tracee
+                .capture_string(raw)
+                .map_or(ArgValue::Pointer(raw), ArgValue::Path),
+            ArgType::String => tracee
+                .capture_string(raw)
+                .map_or(ArgValue::Pointer(raw), ArgValue::String),
+            ArgType::Flags => ArgValue::Flags(raw),
+        }
+    }
+}
+
+/// Group of related syscalls, same as the classes `strace -e trace=%class` uses
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::Display, strum::VariantArray,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum SyscallClass {
+    /// Syscalls taking a path as an argument
+    File,
+    Network,
+    Process,
+    Memory,
+    Signal,
+    /// System V IPC
+    Ipc,
+}
+
+impl SyscallClass {
+    /// Names of the syscalls in the class
+    #[must_use]
+    pub fn syscalls(self) -> &'static [&'static str] {
+        match self {
+            SyscallClass::File => FILE,
+            SyscallClass::Network => NETWORK,
+            SyscallClass::Process => PROCESS,
+            SyscallClass::Memory => MEMORY,
+            SyscallClass::Signal => SIGNAL,
+            SyscallClass::Ipc => IPC,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallSignature {
+    pub number: u64,
+    pub name: &'static str,
+    pub args: &'static [ArgType],
+}
+
+impl SyscallSignature {
+    #[must_use]
+    pub fn has_class(&self, class: SyscallClass) -> bool {
+        class.syscalls().contains(&self.name)
+    }
+
+    /// Decodes as many arguments as the syscall takes
+    #[must_use]
+    pub fn decode_args(&self, tracee: &mut Tracee, args: &[u64; 6]) -> Vec<ArgValue> {
+        self.args
+            .iter()
+            .zip(args)
+            .map(|(arg_type, raw)| arg_type.decode(tracee, *raw))
+            .collect()
+    }
+}
+
+/// Returns signature of the syscall with the given number
+#[must_use]
+pub fn lookup(number: u64) -> Option<&'static SyscallSignature> {
+    SYSCALLS
+        .binary_search_by_key(&number, |signature| signature.number)
+        .ok()
+        .map(|index| &SYSCALLS[index])
+}
+
+/// Returns signature of the syscall with the given name, like `openat`
+#[must_use]
+pub fn lookup_name(name: &str) -> Option<&'static SyscallSignature> {
+    SYSCALLS.iter().find(|signature| signature.name == name)
+}
+
+#[must_use]
+pub fn syscall_name(number: u64) -> Option<&'static str> {
+    lookup(number).map(|signature| signature.name)
+}
+
+const fn sys(number: u64, name: &'static str, args: &'static [ArgType]) -> SyscallSignature {
+    SyscallSignature { number, name, args }
+}
+
+use ArgType::{Fd, Flags, Int, Path, Pointer, String as Str};
+
+/// All x86-64 syscalls, sorted by their number
+pub static SYSCALLS: &[SyscallSignature] = &[
+    sys(0, "read", &[Fd, Pointer, Int]),
+    sys(1, "write", &[Fd, Pointer, Int]),
+    sys(2, "open", &[Path, Flags, Flags]),
+    sys(3, "close", &[Fd]),
+    sys(4, "stat", &[Path, Pointer]),
+    sys(5, "fstat", &[Fd, Pointer]),
+    sys(6, "lstat", &[Path, Pointer]),
+    sys(7, "poll", &[Pointer, Int, Int]),
+    sys(8, "lseek", &[Fd, Int, Int]),
+    sys(9, "mmap", &[Pointer, Int, Flags, Flags, Fd, Int]),
+    sys(10, "mprotect", &[Pointer, Int, Flags]),
+    sys(11, "munmap", &[Pointer, Int]),
+    sys(12, "brk", &[Pointer]),
+    sys(13, "rt_sigaction", &[Int, Pointer, Pointer, Int]),
+    sys(14, "rt_sigprocmask", &[Int, Pointer, Pointer, Int]),
+    sys(15, "rt_sigreturn", &[]),
+    sys(16, "ioctl", &[Fd, Int, Pointer]),
+    sys(17, "pread64", &[Fd, Pointer, Int, Int]),
+    sys(18, "pwrite64", &[Fd, Pointer, Int, Int]),
+    sys(19, "readv", &[Fd, Pointer, Int]),
+    sys(20, "writev", &[Fd, Pointer, Int]),
+    sys(21, "access", &[Path, Flags]),
+    sys(22, "pipe", &[Pointer]),
+    sys(23, "select", &[Int, Pointer, Pointer, Pointer, Pointer]),
+    sys(24, "sched_yield", &[]),
+    sys(25, "mremap", &[Pointer, Int, Int, Flags, Pointer]),
+    sys(26, "msync", &[Pointer, Int, Flags]),
+    sys(27, "mincore", &[Pointer, Int, Pointer]),
+    sys(28, "madvise", &[Pointer, Int, Int]),
+    sys(29, "shmget", &[Int, Int, Flags]),
+    sys(30, "shmat", &[Int, Pointer, Flags]),
+    sys(31, "shmctl", &[Int, Int, Pointer]),
+    sys(32, "dup", &[Fd]),
+    sys(33, "dup2", &[Fd, Fd]),
+    sys(34, "pause", &[]),
+    sys(35, "nanosleep", &[Pointer, Pointer]),
+    sys(36, "getitimer", &[Int, Pointer]),
+    sys(37, "alarm", &[Int]),
+    sys(38, "setitimer", &[Int, Pointer, Pointer]),
+    sys(39, "getpid", &[]),
+    sys(40, "sendfile", &[Fd, Fd, Pointer, Int]),
+    sys(41, "socket", &[Int, Int, Int]),
+    sys(42, "connect", &[Fd, Pointer, Int]),
+    sys(43, "accept", &[Fd, Pointer, Pointer]),
+    sys(44, "sendto", &[Fd, Pointer, Int, Flags, Pointer, Int]),
+    sys(45, "recvfrom", &[Fd, Pointer, Int, Flags, Pointer, Pointer]),
+    sys(46, "sendmsg", &[Fd, Pointer, Flags]),
+    sys(47, "recvmsg", &[Fd, Pointer, Flags]),
+    sys(48, "shutdown", &[Fd, Int]),
+    sys(49, "bind", &[Fd, Pointer, Int]),
+    sys(50, "listen", &[Fd, Int]),
+    sys(51, "getsockname", &[Fd, Pointer, Pointer]),
+    sys(52, "getpeername", &[Fd, Pointer, Pointer]),
+    sys(53, "socketpair", &[Int, Int, Int, Pointer]),
+    sys(54, "setsockopt", &[Fd, Int, Int, Pointer, Int]),
+    sys(55, "getsockopt", &[Fd, Int, Int, Pointer, Pointer]),
+    sys(56, "clone", &[Flags, Pointer, Pointer, Pointer, Int]),
+    sys(57, "fork", &[]),
+    sys(58, "vfork", &[]),
+    sys(59, "execve", &[Path, Pointer, Pointer]),
+    sys(60, "exit", &[Int]),
+    sys(61, "wait4", &[Int, Pointer, Flags, Pointer]),
+    sys(62, "kill", &[Int, Int]),
+    sys(63, "uname", &[Pointer]),
+    sys(64, "semget", &[Int, Int, Flags]),
+    sys(65, "semop", &[Int, Pointer, Int]),
+    sys(66, "semctl", &[Int, Int, Int, Pointer]),
+    sys(67, "shmdt", &[Pointer]),
+    sys(68, "msgget", &[Int, Flags]),
+    sys(69, "msgsnd", &[Int, Pointer, Int, Flags]),
+    sys(70, "msgrcv", &[Int, Pointer, Int, Int, Flags]),
+    sys(71, "msgctl", &[Int, Int, Pointer]),
+    sys(72, "fcntl", &[Fd, Int, Int]),
+    sys(73, "flock", &[Fd, Int]),
+    sys(74, "fsync", &[Fd]),
+    sys(75, "fdatasync", &[Fd]),
+    sys(76, "truncate", &[Path, Int]),
+    sys(77, "ftruncate", &[Fd, Int]),
+    sys(78, "getdents", &[Fd, Pointer, Int]),
+    sys(79, "getcwd", &[Pointer, Int]),
+    sys(80, "chdir", &[Path]),
+    sys(81, "fchdir", &[Fd]),
+    sys(82, "rename", &[Path, Path]),
+    sys(83, "mkdir", &[Path, Flags]),
+    sys(84, "rmdir", &[Path]),
+    sys(85, "creat", &[Path, Flags]),
+    sys(86, "link", &[Path, Path]),
+    sys(87, "unlink", &[Path]),
+    sys(88, "symlink", &[Path, Path]),
+    sys(89, "readlink", &[Path, Pointer, Int]),
+    sys(90, "chmod", &[Path, Flags]),
+    sys(91, "fchmod", &[Fd, Flags]),
+    sys(92, "chown", &[Path, Int, Int]),
+    sys(93, "fchown", &[Fd, Int, Int]),
+    sys(94, "lchown", &[Path, Int, Int]),
+    sys(95, "umask", &[Flags]),
+    sys(96, "gettimeofday", &[Pointer, Pointer]),
+    sys(97, "getrlimit", &[Int, Pointer]),
+    sys(98, "getrusage", &[Int, Pointer]),
+    sys(99, "sysinfo", &[Pointer]),
+    sys(100, "times", &[Pointer]),
+    sys(101, "ptrace", &[Int, Int, Pointer, Pointer]),
+    sys(102, "getuid", &[]),
+    sys(103, "syslog", &[Int, Pointer, Int]),
+    sys(104, "getgid", &[]),
+    sys(105, "setuid", &[Int]),
+    sys(106, "setgid", &[Int]),
+    sys(107, "geteuid", &[]),
+    sys(108, "getegid", &[]),
+    sys(109, "setpgid", &[Int, Int]),
+    sys(110, "getppid", &[]),
+    sys(111, "getpgrp", &[]),
+    sys(112, "setsid", &[]),
+    sys(113, "setreuid", &[Int, Int]),
+    sys(114, "setregid", &[Int, Int]),
+    sys(115, "getgroups", &[Int, Pointer]),
+    sys(116, "setgroups", &[Int, Pointer]),
+    sys(117, "setresuid", &[Int, Int, Int]),
+    sys(118, "getresuid", &[Pointer, Pointer, Pointer]),
+    sys(119, "setresgid", &[Int, Int, Int]),
+    sys(120, "getresgid", &[Pointer, Pointer, Pointer]),
+    sys(121, "getpgid", &[Int]),
+    sys(122, "setfsuid", &[Int]),
+    sys(123, "setfsgid", &[Int]),
+    sys(124, "getsid", &[Int]),
+    sys(125, "capget", &[Pointer, Pointer]),
+    sys(126, "capset", &[Pointer, Pointer]),
+    sys(127, "rt_sigpending", &[Pointer, Int]),
+    sys(128, "rt_sigtimedwait", &[Pointer, Pointer, Pointer, Int]),
+    sys(129, "rt_sigqueueinfo", &[Int, Int, Pointer]),
+    sys(130, "rt_sigsuspend", &[Pointer, Int]),
+    sys(131, "sigaltstack", &[Pointer, Pointer]),
+    sys(132, "utime", &[Path, Pointer]),
+    sys(133, "mknod", &[Path, Flags, Int]),
+    sys(134, "uselib", &[Path]),
+    sys(135, "personality", &[Int]),
+    sys(136, "ustat", &[Int, Pointer]),
+    sys(137, "statfs", &[Path, Pointer]),
+    sys(138, "fstatfs", &[Fd, Pointer]),
+    sys(139, "sysfs", &[Int, Int, Int]),
+    sys(140, "getpriority", &[Int, Int]),
+    sys(141, "setpriority", &[Int, Int, Int]),
+    sys(142, "sched_setparam", &[Int, Pointer]),
+    sys(143, "sched_getparam", &[Int, Pointer]),
+    sys(144, "sched_setscheduler", &[Int, Int, Pointer]),
+    sys(145, "sched_getscheduler", &[Int]),
+    sys(146, "sched_get_priority_max", &[Int]),
+    sys(147, "sched_get_priority_min", &[Int]),
+    sys(148, "sched_rr_get_interval", &[Int, Pointer]),
+    sys(149, "mlock", &[Pointer, Int]),
+    sys(150, "munlock", &[Pointer, Int]),
+    sys(151, "mlockall", &[Flags]),
+    sys(152, "munlockall", &[]),
+    sys(153, "vhangup", &[]),
+    sys(154, "modify_ldt", &[Int, Pointer, Int]),
+    sys(155, "pivot_root", &[Path, Path]),
+    sys(156, "_sysctl", &[Pointer]),
+    sys(157, "prctl", &[Int, Int, Int, Int, Int]),
+    sys(158, "arch_prctl", &[Int, Pointer]),
+    sys(159, "adjtimex", &[Pointer]),
+    sys(160, "setrlimit", &[Int, Pointer]),
+    sys(161, "chroot", &[Path]),
+    sys(162, "sync", &[]),
+    sys(163, "acct", &[Path]),
+    sys(164, "settimeofday", &[Pointer, Pointer]),
+    sys(165, "mount", &[Str, Path, Str, Flags, Pointer]),
+    sys(166, "umount2", &[Path, Flags]),
+    sys(167, "swapon", &[Path, Flags]),
+    sys(168, "swapoff", &[Path]),
+    sys(169, "reboot", &[Int, Int, Int, Pointer]),
+    sys(170, "sethostname", &[Str, Int]),
+    sys(171, "setdomainname", &[Str, Int]),
+    sys(172, "iopl", &[Int]),
+    sys(173, "ioperm", &[Int, Int, Int]),
+    sys(174, "create_module", &[Str, Int]),
+    sys(175, "init_module", &[Pointer, Int, Str]),
+    sys(176, "delete_module", &[Str, Flags]),
+    sys(177, "get_kernel_syms", &[Pointer]),
+    sys(178, "query_module", &[Str, Int, Pointer, Int, Pointer]),
+    sys(179, "quotactl", &[Int, Path, Int, Pointer]),
+    sys(180, "nfsservctl", &[]),
+    sys(181, "getpmsg", &[]),
+    sys(182, "putpmsg", &[]),
+    sys(183, "afs_syscall", &[]),
+    sys(184, "tuxcall", &[]),
+    sys(185, "security", &[]),
+    sys(186, "gettid", &[]),
+    sys(187, "readahead", &[Fd, Int, Int]),
+    sys(188, "setxattr", &[Path, Str, Pointer, Int, Flags]),
+    sys(189, "lsetxattr", &[Path, Str, Pointer, Int, Flags]),
+    sys(190, "fsetxattr", &[Fd, Str, Pointer, Int, Flags]),
+    sys(191, "getxattr", &[Path, Str, Pointer, Int]),
+    sys(192, "lgetxattr", &[Path, Str, Pointer, Int]),
+    sys(193, "fgetxattr", &[Fd, Str, Pointer, Int]),
+    sys(194, "listxattr", &[Path, Pointer, Int]),
+    sys(195, "llistxattr", &[Path, Pointer, Int]),
+    sys(196, "flistxattr", &[Fd, Pointer, Int]),
+    sys(197, "removexattr", &[Path, Str]),
+    sys(198, "lremovexattr", &[Path, Str]),
+    sys(199, "fremovexattr", &[Fd, Str]),
+    sys(200, "tkill", &[Int, Int]),
+    sys(201, "time", &[Pointer]),
+    sys(202, "futex", &[Pointer, Int, Int, Pointer, Pointer, Int]),
+    sys(203, "sched_setaffinity", &[Int, Int, Pointer]),
+    sys(204, "sched_getaffinity", &[Int, Int, Pointer]),
+    sys(205, "set_thread_area", &[Pointer]),
+    sys(206, "io_setup", &[Int, Pointer]),
+    sys(207, "io_destroy", &[Int]),
+    sys(208, "io_getevents", &[Int, Int, Int, Pointer, Pointer]),
+    sys(209, "io_submit", &[Int, Int, Pointer]),
+    sys(210, "io_cancel", &[Int, Pointer, Pointer]),
+    sys(211, "get_thread_area", &[Pointer]),
+    sys(212, "lookup_dcookie", &[Int, Pointer, Int]),
+    sys(213, "epoll_create", &[Int]),
+    sys(214, "epoll_ctl_old", &[]),
+    sys(215, "epoll_wait_old", &[]),
+    sys(216, "remap_file_pages", &[Pointer, Int, Int, Int, Flags]),
+    sys(217, "getdents64", &[Fd, Pointer, Int]),
+    sys(218, "set_tid_address", &[Pointer]),
+    sys(219, "restart_syscall", &[]),
+    sys(220, "semtimedop", &[Int, Pointer, Int, Pointer]),
+    sys(221, "fadvise64", &[Fd, Int, Int, Int]),
+    sys(222, "timer_create", &[Int, Pointer, Pointer]),
+    sys(223, "timer_settime", &[Int, Flags, Pointer, Pointer]),
+    sys(224, "timer_gettime", &[Int, Pointer]),
+    sys(225, "timer_getoverrun", &[Int]),
+    sys(226, "timer_delete", &[Int]),
+    sys(227, "clock_settime", &[Int, Pointer]),
+    sys(228, "clock_gettime", &[Int, Pointer]),
+    sys(229, "clock_getres", &[Int, Pointer]),
+    sys(230, "clock_nanosleep", &[Int, Flags, Pointer, Pointer]),
+    sys(231, "exit_group", &[Int]),
+    sys(232, "epoll_wait", &[Fd, Pointer, Int, Int]),
+    sys(233, "epoll_ctl", &[Fd, Int, Fd, Pointer]),
+    sys(234, "tgkill", &[Int, Int, Int]),
+    sys(235, "utimes", &[Path, Pointer]),
+    sys(236, "vserver", &[]),
+    sys(237, "mbind", &[Pointer, Int, Int, Pointer, Int, Flags]),
+    sys(238, "set_mempolicy", &[Int, Pointer, Int]),
+    sys(
+        239,
+        "get_mempolicy",
+        &[Pointer, Pointer, Int, Pointer, Flags],
+    ),
+    sys(240, "mq_open", &[Str, Flags, Flags, Pointer]),
+    sys(241, "mq_unlink", &[Str]),
+    sys(242, "mq_timedsend", &[Int, Pointer, Int, Int, Pointer]),
+    sys(
+        243,
+        "mq_timedreceive",
+        &[Int, Pointer, Int, Pointer, Pointer],
+    ),
+    sys(244, "mq_notify", &[Int, Pointer]),
+    sys(245, "mq_getsetattr", &[Int, Pointer, Pointer]),
+    sys(246, "kexec_load", &[Int, Int, Pointer, Flags]),
+    sys(247, "waitid", &[Int, Int, Pointer, Flags, Pointer]),
+    sys(248, "add_key", &[Str, Str, Pointer, Int, Int]),
+    sys(249, "request_key", &[Str, Str, Str, Int]),
+    sys(250, "keyctl", &[Int, Int, Int, Int, Int]),
+    sys(251, "ioprio_set", &[Int, Int, Int]),
+    sys(252, "ioprio_get", &[Int, Int]),
+    sys(253, "inotify_init", &[]),
+    sys(254, "inotify_add_watch", &[Fd, Path, Flags]),
+    sys(255, "inotify_rm_watch", &[Fd, Int]),
+    sys(256, "migrate_pages", &[Int, Int, Pointer, Pointer]),
+    sys(257, "openat", &[Fd, Path, Flags, Flags]),
+    sys(258, "mkdirat", &[Fd, Path, Flags]),
+    sys(259, "mknodat", &[Fd, Path, Flags, Int]),
+    sys(260, "fchownat", &[Fd, Path, Int, Int, Flags]),
+    sys(261, "futimesat", &[Fd, Path, Pointer]),
+    sys(262, "newfstatat", &[Fd, Path, Pointer, Flags]),
+    sys(263, "unlinkat", &[Fd, Path, Flags]),
+    sys(264, "renameat", &[Fd, Path, Fd, Path]),
+    sys(265, "linkat", &[Fd, Path, Fd, Path, Flags]),
+    sys(266, "symlinkat", &[Path, Fd, Path]),
+    sys(267, "readlinkat", &[Fd, Path, Pointer, Int]),
+    sys(268, "fchmodat", &[Fd, Path, Flags]),
+    sys(269, "faccessat", &[Fd, Path, Flags]),
+    sys(
+        270,
+        "pselect6",
+        &[Int, Pointer, Pointer, Pointer, Pointer, Pointer],
+    ),
+    sys(271, "ppoll", &[Pointer, Int, Pointer, Pointer, Int]),
+    sys(272, "unshare", &[Flags]),
+    sys(273, "set_robust_list", &[Pointer, Int]),
+    sys(274, "get_robust_list", &[Int, Pointer, Pointer]),
+    sys(275, "splice", &[Fd, Pointer, Fd, Pointer, Int, Flags]),
+    sys(276, "tee", &[Fd, Fd, Int, Flags]),
+    sys(277, "sync_file_range", &[Fd, Int, Int, Flags]),
+    sys(278, "vmsplice", &[Fd, Pointer, Int, Flags]),
+    sys(
+        279,
+        "move_pages",
+        &[Int, Int, Pointer, Pointer, Pointer, Flags],
+    ),
+    sys(280, "utimensat", &[Fd, Path, Pointer, Flags]),
+    sys(281, "epoll_pwait", &[Fd, Pointer, Int, Int, Pointer, Int]),
+    sys(282, "signalfd", &[Fd, Pointer, Int]),
+    sys(283, "timerfd_create", &[Int, Flags]),
+    sys(284, "eventfd", &[Int]),
+    sys(285, "fallocate", &[Fd, Flags, Int, Int]),
+    sys(286, "timerfd_settime", &[Fd, Flags, Pointer, Pointer]),
+    sys(287, "timerfd_gettime", &[Fd, Pointer]),
+    sys(288, "accept4", &[Fd, Pointer, Pointer, Flags]),
+    sys(289, "signalfd4", &[Fd, Pointer, Int, Flags]),
+    sys(290, "eventfd2", &[Int, Flags]),
+    sys(291, "epoll_create1", &[Flags]),
+    sys(292, "dup3", &[Fd, Fd, Flags]),
+    sys(293, "pipe2", &[Pointer, Flags]),
+    sys(294, "inotify_init1", &[Flags]),
+    sys(295, "preadv", &[Fd, Pointer, Int, Int, Int]),
+    sys(296, "pwritev", &[Fd, Pointer, Int, Int, Int]),
+    sys(297, "rt_tgsigqueueinfo", &[Int, Int, Int, Pointer]),
+    sys(298, "perf_event_open", &[Pointer, Int, Int, Fd, Flags]),
+    sys(299, "recvmmsg", &[Fd, Pointer, Int, Flags, Pointer]),
+    sys(300, "fanotify_init", &[Flags, Flags]),
+    sys(301, "fanotify_mark", &[Fd, Flags, Flags, Fd, Path]),
+    sys(302, "prlimit64", &[Int, Int, Pointer, Pointer]),
+    sys(
+        303,
+        "name_to_handle_at",
+        &[Fd, Path, Pointer, Pointer, Flags],
+    ),
+    sys(304, "open_by_handle_at", &[Fd, Pointer, Flags]),
+    sys(305, "clock_adjtime", &[Int, Pointer]),
+    sys(306, "syncfs", &[Fd]),
+    sys(307, "sendmmsg", &[Fd, Pointer, Int, Flags]),
+    sys(308, "setns", &[Fd, Flags]),
+    sys(309, "getcpu", &[Pointer, Pointer, Pointer]),
+    sys(
+        310,
+        "process_vm_readv",
+        &[Int, Pointer, Int, Pointer, Int, Flags],
+    ),
+    sys(
+        311,
+        "process_vm_writev",
+        &[Int, Pointer, Int, Pointer, Int, Flags],
+    ),
+    sys(312, "kcmp", &[Int, Int, Int, Int, Int]),
+    sys(313, "finit_module", &[Fd, Str, Flags]),
+    sys(314, "sched_setattr", &[Int, Pointer, Flags]),
+    sys(315, "sched_getattr", &[Int, Pointer, Int, Flags]),
+    sys(316, "renameat2", &[Fd, Path, Fd, Path, Flags]),
+    sys(317, "seccomp", &[Int, Flags, Pointer]),
+    sys(318, "getrandom", &[Pointer, Int, Flags]),
+    sys(319, "memfd_create", &[Str, Flags]),
+    sys(320, "kexec_file_load", &[Fd, Fd, Int, Str, Flags]),
+    sys(321, "bpf", &[Int, Pointer, Int]),
+    sys(322, "execveat", &[Fd, Path, Pointer, Pointer, Flags]),
+    sys(323, "userfaultfd", &[Flags]),
+    sys(324, "membarrier", &[Int, Flags, Int]),
+    sys(325, "mlock2", &[Pointer, Int, Flags]),
+    sys(
+        326,
+        "copy_file_range",
+        &[Fd, Pointer, Fd, Pointer, Int, Flags],
+    ),
+    sys(327, "preadv2", &[Fd, Pointer, Int, Int, Int, Flags]),
+    sys(328, "pwritev2", &[Fd, Pointer, Int, Int, Int, Flags]),
+    sys(329, "pkey_mprotect", &[Pointer, Int, Flags, Int]),
+    sys(330, "pkey_alloc", &[Flags, Flags]),
+    sys(331, "pkey_free", &[Int]),
+    sys(332, "statx", &[Fd, Path, Flags, Flags, Pointer]),
+    sys(
+        333,
+        "io_pgetevents",
+        &[Int, Int, Int, Pointer, Pointer, Pointer],
+    ),
+    sys(334, "rseq", &[Pointer, Int, Flags, Int]),
+    sys(424, "pidfd_send_signal", &[Fd, Int, Pointer, Flags]),
+    sys(425, "io_uring_setup", &[Int, Pointer]),
+    sys(426, "io_uring_enter", &[Fd, Int, Int, Flags, Pointer, Int]),
+    sys(427, "io_uring_register", &[Fd, Int, Pointer, Int]),
+    sys(428, "open_tree", &[Fd, Path, Flags]),
+    sys(429, "move_mount", &[Fd, Path, Fd, Path, Flags]),
+    sys(430, "fsopen", &[Str, Flags]),
+    sys(431, "fsconfig", &[Fd, Int, Str, Pointer, Int]),
+    sys(432, "fsmount", &[Fd, Flags, Flags]),
+    sys(433, "fspick", &[Fd, Path, Flags]),
+    sys(434, "pidfd_open", &[Int, Flags]),
+    sys(435, "clone3", &[Pointer, Int]),
+    sys(436, "close_range", &[Fd, Fd, Flags]),
+    sys(437, "openat2", &[Fd, Path, Pointer, Int]),
+    sys(438, "pidfd_getfd", &[Fd, Fd, Flags]),
+    sys(439, "faccessat2", &[Fd, Path, Flags, Flags]),
+    sys(440, "process_madvise", &[Fd, Pointer, Int, Int, Flags]),
+    sys(
+        441,
+        "epoll_pwait2",
+        &[Fd, Pointer, Int, Pointer, Pointer, Int],
+    ),
+    sys(442, "mount_setattr", &[Fd, Path, Flags, Pointer, Int]),
+    sys(443, "quotactl_fd", &[Fd, Int, Int, Pointer]),
+    sys(444, "landlock_create_ruleset", &[Pointer, Int, Flags]),
+    sys(445, "landlock_add_rule", &[Fd, Int, Pointer, Flags]),
+    sys(446, "landlock_restrict_self", &[Fd, Flags]),
+    sys(447, "memfd_secret", &[Flags]),
+    sys(448, "process_mrelease", &[Fd, Flags]),
+    sys(449, "futex_waitv", &[Pointer, Int, Flags, Pointer, Int]),
+    sys(450, "set_mempolicy_home_node", &[Pointer, Int, Int, Flags]),
+    sys(451, "cachestat", &[Fd, Pointer, Pointer, Flags]),
+    sys(452, "fchmodat2", &[Fd, Path, Flags, Flags]),
+    sys(453, "map_shadow_stack", &[Pointer, Int, Flags]),
+    sys(454, "futex_wake", &[Pointer, Int, Int, Flags]),
+    sys(455, "futex_wait", &[Pointer, Int, Int, Flags, Pointer, Int]),
+    sys(456, "futex_requeue", &[Pointer, Flags, Int, Int]),
+    sys(457, "statmount", &[Pointer, Pointer, Int, Flags]),
+    sys(458, "listmount", &[Pointer, Pointer, Int, Flags]),
+    sys(459, "lsm_get_self_attr", &[Int, Pointer, Pointer, Flags]),
+    sys(460, "lsm_set_self_attr", &[Int, Pointer, Int, Flags]),
+    sys(461, "lsm_list_modules", &[Pointer, Pointer, Flags]),
+    sys(462, "mseal", &[Pointer, Int, Flags]),
+    sys(463, "setxattrat", &[Fd, Path, Flags, Str, Pointer, Int]),
+    sys(464, "getxattrat", &[Fd, Path, Flags, Str, Pointer, Int]),
+    sys(465, "listxattrat", &[Fd, Path, Flags, Pointer, Int]),
+    sys(466, "removexattrat", &[Fd, Path, Flags, Str]),
+    sys(467, "open_tree_attr", &[Fd, Path, Flags, Pointer, Int]),
+    sys(468, "file_getattr", &[Fd, Path, Pointer, Int, Flags]),
+    sys(469, "file_setattr", &[Fd, Path, Pointer, Int, Flags]),
+];
+
+// syscalls of each SyscallClass, grouped the way strace groups them
+const FILE: &[&str] = &[
+    "open",
+    "stat",
+    "lstat",
+    "access",
+    "execve",
+    "truncate",
+    "chdir",
+    "rename",
+    "mkdir",
+    "rmdir",
+    "creat",
+    "link",
+    "unlink",
+    "symlink",
+    "readlink",
+    "chmod",
+    "chown",
+    "lchown",
+    "utime",
+    "mknod",
+    "uselib",
+    "statfs",
+    "pivot_root",
+    "chroot",
+    "acct",
+    "mount",
+    "umount2",
+    "swapon",
+    "swapoff",
+    "quotactl",
+    "setxattr",
+    "lsetxattr",
+    "getxattr",
+    "lgetxattr",
+    "listxattr",
+    "llistxattr",
+    "removexattr",
+    "lremovexattr",
+    "utimes",
+    "inotify_add_watch",
+    "openat",
+    "mkdirat",
+    "mknodat",
+    "fchownat",
+    "futimesat",
+    "newfstatat",
+    "unlinkat",
+    "renameat",
+    "linkat",
+    "symlinkat",
+    "readlinkat",
+    "fchmodat",
+    "faccessat",
+    "utimensat",
+    "fanotify_mark",
+    "name_to_handle_at",
+    "renameat2",
+    "execveat",
+    "statx",
+    "open_tree",
+    "move_mount",
+    "fspick",
+    "openat2",
+    "faccessat2",
+    "mount_setattr",
+    "fchmodat2",
+    "setxattrat",
+    "getxattrat",
+    "listxattrat",
+    "removexattrat",
+    "open_tree_attr",
+    "file_getattr",
+    "file_setattr",
+];
+
+const NETWORK: &[&str] = &[
+    "socket",
+    "connect",
+    "accept",
+    "sendto",
+    "recvfrom",
+    "sendmsg",
+    "recvmsg",
+    "shutdown",
+    "bind",
+    "listen",
+    "getsockname",
+    "getpeername",
+    "socketpair",
+    "setsockopt",
+    "getsockopt",
+    "accept4",
+    "recvmmsg",
+    "sendmmsg",
+];
+
+const PROCESS: &[&str] = &[
+    "clone",
+    "fork",
+    "vfork",
+    "execve",
+    "exit",
+    "wait4",
+    "kill",
+    "rt_sigqueueinfo",
+    "tkill",
+    "exit_group",
+    "tgkill",
+    "waitid",
+    "unshare",
+    "rt_tgsigqueueinfo",
+    "execveat",
+    "pidfd_send_signal",
+    "pidfd_open",
+    "clone3",
+    "pidfd_getfd",
+];
+
+const MEMORY: &[&str] = &[
+    "mmap",
+    "mprotect",
+    "munmap",
+    "brk",
+    "mremap",
+    "msync",
+    "mincore",
+    "madvise",
+    "shmat",
+    "shmdt",
+    "mlock",
+    "munlock",
+    "mlockall",
+    "munlockall",
+    "remap_file_pages",
+    "mbind",
+    "set_mempolicy",
+    "get_mempolicy",
+    "migrate_pages",
+    "move_pages",
+    "mlock2",
+    "pkey_mprotect",
+    "process_madvise",
+    "set_mempolicy_home_node",
+    "map_shadow_stack",
+    "mseal",
+];
+
+const SIGNAL: &[&str] = &[
+    "rt_sigaction",
+    "rt_sigprocmask",
+    "rt_sigreturn",
+    "pause",
+    "kill",
+    "rt_sigpending",
+    "rt_sigtimedwait",
+    "rt_sigqueueinfo",
+    "rt_sigsuspend",
+    "sigaltstack",
+    "tkill",
+    "tgkill",
+    "signalfd",
+    "signalfd4",
+    "rt_tgsigqueueinfo",
+    "pidfd_send_signal",
+];
+
+const IPC: &[&str] = &[
+    "shmget",
+    "shmat",
+    "shmctl",
+    "semget",
+    "semop",
+    "semctl",
+    "shmdt",
+    "msgget",
+    "msgsnd",
+    "msgrcv",
+    "msgctl",
+    "semtimedop",
+];
@@ -0,0 +1,262 @@
+//! On-disk format of saved traces
+//!
+//! A trace file starts with a 16 byte header: [`MAGIC`], the format version as a little-endian
+//! `u32` and 4 reserved zero bytes. The rest is the trace archived by rkyv. Files written before
+//! the header was introduced are read as version 0, with the types of the `v0` module, and have
+//! no [`TraceMetadata`].
+//!
+//! Every change of the archived types bumps [`FORMAT_VERSION`], so an older reader reports
+//! [`TraceFileError::UnsupportedVersion`] for a newer file instead of failing on data it doesn't
+//! know. Variants of archived enums, like [`SyscallInfo`](crate::syscall::SyscallInfo) and
+//! [`TraceEvent`](crate::syscall::parse_error::TraceEvent), have explicit tags. A new variant
+//! takes the next unused tag, files of the previous version are then read with the current
+//! types. After any other change, the previous types are kept in a module of their own and
+//! [`from_bytes`] converts traces of that version to the current types.
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use nix::{sys::utsname::uname, unistd::Pid};
+use rkyv::util::AlignedVec;
+use thiserror::Error;
+
+use crate::syscall::{
+    SyscallIterOpts, SyscallNewTypeError, TraceData, capture::CaptureLimits, replay::Replay,
+};
+
+mod v0;
+
+/// First bytes of every trace file
+pub const MAGIC: [u8; 8] = *b"BOUBOTRC";
+/// Version of the format written by this build
+pub const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum TraceFileError {
+    #[error(
+        "trace file has format version {0}, but only versions up to {FORMAT_VERSION} are \
+         supported, it was written by a newer version of boubo_trace"
+    )]
+    UnsupportedVersion(u32),
+    #[error("trace file of format version {version} is corrupted: {source}")]
+    Invalid {
+        version: u32,
+        source: SyscallNewTypeError,
+    },
+    #[error("file has no trace header and isn't a trace written before the header was introduced")]
+    NotATrace(#[source] SyscallNewTypeError),
+    #[error("could not serialize the trace: {0}")]
+    Serialize(#[source] SyscallNewTypeError),
+}
+
+/// Description of a traced program and how it was traced, saved with its trace
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+#[rkyv(derive(Debug))]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "these are options of the tracing"
+)]
+pub struct TraceMetadata {
+    /// Path of the traced program
+    pub executable: Vec<u8>,
+    /// Arguments of the program, without its name
+    pub args: Vec<Vec<u8>>,
+    /// Working directory of the program
+    pub work_dir: Vec<u8>,
+    /// Environment of the program, as `NAME=value` entries
+    pub env: Vec<Vec<u8>>,
+    /// Realtime (Unix epoch) time when tracing started
+    pub start_time: Duration,
+    pub hostname: String,
+    /// Kernel release, like `6.8.0-45-generic`
+    pub kernel_version: String,
+    /// CPU architecture, like `x86_64`
+    pub arch: String,
+    pub skip_to_main: bool,
+    pub skip_to_main_after_exec: bool,
+    /// [`SyscallFilter`](crate::syscall::filter::SyscallFilter) expression of the traced
+    /// syscalls
+    pub filter: String,
+    pub seccomp: bool,
+    pub realtime_timestamps: bool,
+    pub stack_depth: usize,
+    /// Limits of the captured buffers and strings
+    pub capture_limits: CaptureLimits,
+    /// Rules of injected faults, formatted like they are parsed, e.g. `openat:error=ENOENT`
+    pub faults: Vec<String>,
+    /// Rules of injected delays, like `write:delay=200ms:at=exit`
+    pub delays: Vec<String>,
+    /// Rules of tampered results, like `getpid:return=42`
+    pub tampers: Vec<String>,
+    /// Number of items in the trace replayed to the program, `None` if nothing was replayed
+    pub replay: Option<usize>,
+    /// Command line of the tracer, empty if it wasn't run from one
+    pub tracer_args: Vec<String>,
+    /// Raw wait status the program exited with, `None` if it didn't exit while it was traced
+    pub exit_status: Option<i32>,
+}
+
+impl TraceMetadata {
+    /// Describes `command` spawned as `pid` and traced with `opts`, tracing starts now
+    ///
+    /// The executable, working directory and environment are read from procfs, so they are
+    /// what the program got, not what was passed to `command`.
+    #[must_use]
+    pub fn new(pid: Pid, command: &Command, opts: &SyscallIterOpts) -> Self {
+        let proc_link = |name| {
+            fs::read_link(format!("/proc/{pid}/{name}"))
+                .map(|path| path.into_os_string().into_vec())
+                .inspect_err(|err| warn!("could not read /proc/{pid}/{name}: {err}"))
+                .ok()
+        };
+        let (hostname, kernel_version, arch) = match uname() {
+            Ok(uts) => (
+                uts.nodename().to_string_lossy().into_owned(),
+                uts.release().to_string_lossy().into_owned(),
+                uts.machine().to_string_lossy().into_owned(),
+            ),
+            Err(err) => {
+                warn!("could not get the hostname and kernel version: {err}");
+                Default::default()
+            }
+        };
+        let env = match fs::read(format!("/proc/{pid}/environ")) {
+            Ok(environ) => environ
+                .split(|byte| *byte == 0)
+                .filter(|entry| !entry.is_empty())
+                .map(<[u8]>::to_vec)
+                .collect(),
+            Err(err) => {
+                warn!("could not read /proc/{pid}/environ: {err}");
+                command_env(command)
+            }
+        };
+        let injector = &opts.injector;
+        Self {
+            executable: proc_link("exe")
+                .unwrap_or_else(|| command.get_program().as_bytes().to_vec()),
+            args: command
+                .get_args()
+                .map(|arg| arg.as_bytes().to_vec())
+                .collect(),
+            work_dir: proc_link("cwd").unwrap_or_default(),
+            env,
+            start_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            hostname,
+            kernel_version,
+            arch,
+            skip_to_main: opts.skip_to_main,
+            skip_to_main_after_exec: opts.skip_to_main_after_exec,
+            filter: opts.filter.to_string(),
+            seccomp: opts.seccomp,
+            realtime_timestamps: opts.realtime_timestamps,
+            stack_depth: opts.stack_depth,
+            capture_limits: opts.capture_limits,
+            faults: injector.faults().iter().map(ToString::to_string).collect(),
+            delays: injector.delays().iter().map(ToString::to_string).collect(),
+            tampers: injector.tampers().iter().map(ToString::to_string).collect(),
+            replay: injector.recording().map(Replay::trace_len),
+            tracer_args: vec![],
+            exit_status: None,
+        }
+    }
+}
+
+/// Environment `command` passes to its program, used if it can't be read from procfs
+///
+/// Variables removed by [`Command::env_clear`] are included, the command doesn't tell it was
+/// called.
+fn command_env(command: &Command) -> Vec<Vec<u8>> {
+    let mut env: BTreeMap<OsString, OsString> = std::env::vars_os().collect();
+    for (name, value) in command.get_envs() {
+        match value {
+            Some(value) => env.insert(name.to_owned(), value.to_owned()),
+            None => env.remove(name),
+        };
+    }
+    env.into_iter()
+        .map(|(name, value)| {
+            let mut entry = name.into_vec();
+            entry.push(b'=');
+            entry.extend_from_slice(value.as_bytes());
+            entry
+        })
+        .collect()
+}
+
+/// Contents of a trace file
+#[derive(Debug, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct TraceFile {
+    /// `None` for traces saved without metadata, like all traces of version 0
+    pub metadata: Option<TraceMetadata>,
+    pub trace: Vec<TraceData>,
+}
+
+/// Serializes `file` in the current format, with the header
+pub fn to_bytes(file: &TraceFile) -> Result<Vec<u8>, TraceFileError> {
+    let payload = rkyv::to_bytes::<SyscallNewTypeError>(file).map_err(TraceFileError::Serialize)?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Deserializes a trace file of any supported version, converting it to the current types
+pub fn from_bytes(bytes: &[u8]) -> Result<TraceFile, TraceFileError> {
+    let Some((version, payload)) = split_header(bytes) else {
+        let trace = rkyv::from_bytes::<Vec<v0::TraceData>, SyscallNewTypeError>(&aligned(bytes))
+            .map_err(TraceFileError::NotATrace)?;
+        return Ok(TraceFile {
+            metadata: None,
+            trace: trace.into_iter().map(TraceData::from).collect(),
+        });
+    };
+    let payload = aligned(payload);
+    let invalid = |source| TraceFileError::Invalid { version, source };
+    match version {
+        1 => rkyv::from_bytes::<TraceFile, SyscallNewTypeError>(&payload).map_err(invalid),
+        _ => Err(TraceFileError::UnsupportedVersion(version)),
+    }
+}
+
+/// Rewrites a trace file of an older version in the current format
+pub fn upgrade(bytes: &[u8]) -> Result<Vec<u8>, TraceFileError> {
+    to_bytes(&from_bytes(bytes)?)
+}
+
+/// Format version of a trace file, 0 if it has no header
+#[must_use]
+pub fn version(bytes: &[u8]) -> u32 {
+    split_header(bytes).map_or(0, |(version, _)| version)
+}
+
+fn split_header(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let (header, payload) = bytes.split_at_checked(HEADER_LEN)?;
+    let (magic, version) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return None;
+    }
+    let version = u32::from_le_bytes(version[..4].try_into().expect("slice has 4 bytes"));
+    Some((version, payload))
+}
+
+/// Copies `payload` to aligned memory, which archived data need and a slice of a file's bytes
+/// mightn't be
+fn aligned(payload: &[u8]) -> AlignedVec {
+    let mut aligned = AlignedVec::with_capacity(payload.len());
+    aligned.extend_from_slice(payload);
+    aligned
+}
@@ -0,0 +1,316 @@
+//! Types of traces saved before the header was introduced
+//!
+//! They are archived like the types of that build: the variants are in its order, paths and
+//! buffers are plain `Vec<u8>`, the flags of `openat` are the bits of `OFlag` and `Mode` and
+//! syscalls carry the address of their instruction in the ELF file instead of a [`CallSite`].
+//! Only deserializing and converting them to the current types is supported.
+use libc::{sockaddr, socklen_t};
+use nix::{
+    errno::Errno,
+    sys::{signal::Signal, socket::AddressFamily},
+};
+use rkyv::with;
+
+use crate::syscall::{
+    self, CallSite, NewTypeSer, SyscallInfoDiscriminants, SyscallTime,
+    capture::CapturedBytes,
+    flags::{CloneFlags, FileMode, OpenFlags, SocketType},
+    parse_error, sockaddr_ser,
+};
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(super) enum TraceData {
+    Event(TraceEvent),
+    Syscall(Syscall),
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(super) struct Syscall {
+    syscall: SyscallInfo,
+    cpu_time: f64,
+    virt_addr_offset: usize,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(super) enum SyscallInfo {
+    Read {
+        fd: i32,
+        read_bytes: Vec<u8>,
+        requested_count: usize,
+    },
+    Write {
+        fd: i32,
+        to_write: Vec<u8>,
+        written_count: usize,
+    },
+    Close {
+        fd: i32,
+    },
+    Socket {
+        #[rkyv(with = NewTypeSer)]
+        domain: AddressFamily,
+        /// Type and flags were archived as one `i32`
+        r#type: i32,
+        protocol: i32,
+    },
+    Bind {
+        sockfd: i32,
+        #[rkyv(with = with::Map<sockaddr_ser>)]
+        addr: Option<sockaddr>,
+        addrlen: socklen_t,
+    },
+    Listen {
+        sockfd: i32,
+        backlog: i32,
+    },
+    Accept {
+        sockfd: i32,
+        #[rkyv(with = with::Map<sockaddr_ser>)]
+        addr: Option<sockaddr>,
+        addrlen: Option<socklen_t>,
+    },
+    Openat {
+        dirfd: i32,
+        pathname: Vec<u8>,
+        /// Bits of `OFlag`
+        flags: i32,
+        /// Bits of `Mode`
+        mode: u32,
+        opened_fd: i32,
+    },
+    Execve {
+        pathname: Vec<u8>,
+        /// Bytes of the `argv` pointer array up to the first zero byte, not the arguments
+        argv: Vec<u8>,
+        /// Bytes of the `envp` pointer array up to the first zero byte
+        envp: Vec<u8>,
+    },
+    Clone {
+        flags: u64,
+        stack: usize,
+        parent_tid: usize,
+        child_tid: usize,
+        tls: u64,
+    },
+    ExitGroup {
+        status: i32,
+    },
+    Unlink {
+        pathname: Vec<u8>,
+    },
+    Unknown {
+        id: u64,
+        args: [u64; 6],
+        return_value: i64,
+    },
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(super) enum SyscallDisc {
+    Read,
+    Write,
+    Close,
+    Socket,
+    Bind,
+    Listen,
+    Accept,
+    Openat,
+    Execve,
+    Clone,
+    ExitGroup,
+    Unlink,
+    Unknown,
+}
+
+#[derive(rkyv::Archive, rkyv::Deserialize)]
+pub(super) enum TraceEvent {
+    Terminated {
+        #[rkyv(with = NewTypeSer)]
+        signal: Signal,
+        core_dumped: bool,
+    },
+    ProcessExit(i32),
+    SyscallError {
+        syscall: SyscallDisc,
+        #[rkyv(with = NewTypeSer)]
+        error: Errno,
+        cpu_time: f64,
+        rip: u64,
+    },
+}
+
+fn bytes(data: Vec<u8>) -> CapturedBytes {
+    CapturedBytes::new(data, usize::MAX)
+}
+
+impl From<TraceData> for syscall::TraceData {
+    fn from(data: TraceData) -> Self {
+        match data {
+            TraceData::Event(event) => Self::Event(event.into()),
+            TraceData::Syscall(syscall) => Self::Syscall(syscall.into()),
+        }
+    }
+}
+
+impl From<Syscall> for syscall::Syscall {
+    fn from(syscall: Syscall) -> Self {
+        Self {
+            syscall: syscall.syscall.into(),
+            cpu_time: syscall.cpu_time,
+            // the time and the mapped module weren't recorded
+            time: SyscallTime::default(),
+            call_site: CallSite {
+                offset: syscall.virt_addr_offset as u64,
+                ..CallSite::default()
+            },
+            stack: vec![],
+        }
+    }
+}
+
+impl From<SyscallInfo> for syscall::SyscallInfo {
+    fn from(info: SyscallInfo) -> Self {
+        match info {
+            SyscallInfo::Read {
+                fd,
+                read_bytes,
+                requested_count,
+            } => Self::Read {
+                fd,
+                read_bytes: bytes(read_bytes),
+                requested_count,
+            },
+            SyscallInfo::Write {
+                fd,
+                to_write,
+                written_count,
+            } => Self::Write {
+                fd,
+                to_write: bytes(to_write),
+                written_count,
+            },
+            SyscallInfo::Close { fd } => Self::Close { fd },
+            SyscallInfo::Socket {
+                domain,
+                r#type,
+                protocol,
+            } => Self::Socket {
+                domain,
+                r#type: SocketType(r#type),
+                protocol,
+            },
+            SyscallInfo::Bind {
+                sockfd,
+                addr,
+                addrlen,
+            } => Self::Bind {
+                sockfd,
+                addr,
+                addrlen,
+            },
+            SyscallInfo::Listen { sockfd, backlog } => Self::Listen { sockfd, backlog },
+            SyscallInfo::Accept {
+                sockfd,
+                addr,
+                addrlen,
+            } => Self::Accept {
+                sockfd,
+                addr,
+                addrlen,
+            },
+            SyscallInfo::Openat {
+                dirfd,
+                pathname,
+                flags,
+                mode,
+                opened_fd,
+            } => Self::Openat {
+                dirfd,
+                pathname: bytes(pathname),
+                flags: OpenFlags(flags),
+                mode: FileMode(mode),
+                opened_fd,
+            },
+            // the arguments and environment can't be recovered from the pointer arrays
+            SyscallInfo::Execve { pathname, .. } => Self::Execve {
+                pathname: bytes(pathname),
+                argv: vec![],
+                envp: vec![],
+            },
+            SyscallInfo::Clone {
+                flags,
+                stack,
+                parent_tid,
+                child_tid,
+                tls,
+            } => Self::Clone {
+                flags: CloneFlags(flags),
+                stack,
+                parent_tid,
+                child_tid,
+                tls,
+            },
+            SyscallInfo::ExitGroup { status } => Self::ExitGroup { status },
+            SyscallInfo::Unlink { pathname } => Self::Unlink {
+                pathname: bytes(pathname),
+            },
+            SyscallInfo::Unknown {
+                id,
+                args,
+                return_value,
+            } => Self::Unknown {
+                id,
+                args,
+                decoded_args: vec![],
+                return_value,
+            },
+        }
+    }
+}
+
+impl From<SyscallDisc> for SyscallInfoDiscriminants {
+    fn from(syscall: SyscallDisc) -> Self {
+        match syscall {
+            SyscallDisc::Read => Self::Read,
+            SyscallDisc::Write => Self::Write,
+            SyscallDisc::Close => Self::Close,
+            SyscallDisc::Socket => Self::Socket,
+            SyscallDisc::Bind => Self::Bind,
+            SyscallDisc::Listen => Self::Listen,
+            SyscallDisc::Accept => Self::Accept,
+            SyscallDisc::Openat => Self::Openat,
+            SyscallDisc::Execve => Self::Execve,
+            SyscallDisc::Clone => Self::Clone,
+            SyscallDisc::ExitGroup => Self::ExitGroup,
+            SyscallDisc::Unlink => Self::Unlink,
+            SyscallDisc::Unknown => Self::Unknown,
+        }
+    }
+}
+
+impl From<TraceEvent> for parse_error::TraceEvent {
+    fn from(event: TraceEvent) -> Self {
+        match event {
+            TraceEvent::Terminated {
+                signal,
+                core_dumped,
+            } => Self::Terminated {
+                signal,
+                core_dumped,
+            },
+            TraceEvent::ProcessExit(code) => Self::ProcessExit(code),
+            TraceEvent::SyscallError {
+                syscall,
+                error,
+                cpu_time,
+                rip,
+            } => Self::SyscallError {
+                syscall: syscall.into(),
+                error,
+                cpu_time,
+                time: SyscallTime::default(),
+                rip,
+            },
+        }
+    }
+}
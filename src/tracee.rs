@@ -1,31 +1,42 @@
 use std::{
+    collections::HashMap,
     ffi::c_void,
-    fs::File,
-    io::{self, BufRead, BufReader, IoSliceMut},
+    io::{self, IoSlice, IoSliceMut},
     mem,
     os::raw::c_ulonglong,
+    path::{Path, PathBuf},
     sync::LazyLock,
+    time::Duration,
 };
 
 use libc::{
-    PTRACE_EVENT_CLONE, PTRACE_EVENT_EXEC, PTRACE_EVENT_FORK, RAX, c_long, user_regs_struct,
+    PTRACE_EVENT_CLONE, PTRACE_EVENT_EXEC, PTRACE_EVENT_FORK, PTRACE_EVENT_SECCOMP,
+    PTRACE_EVENT_VFORK, RAX, c_long, user_regs_struct,
 };
-use log::{debug, error, trace, warn};
+use log::{debug, trace, warn};
 use nix::{
     errno::Errno,
     sys::{
         ptrace::{self},
         signal::Signal,
-        uio::{RemoteIoVec, process_vm_readv},
-        wait::{WaitStatus, waitpid},
+        uio::{RemoteIoVec, process_vm_readv, process_vm_writev},
+        wait::{WaitPidFlag, WaitStatus, waitpid},
     },
+    time::{ClockId, clock_gettime},
     unistd::Pid,
 };
-use x86_64::registers::debug::{
-    BreakpointCondition, BreakpointSize, DebugAddressRegisterNumber, Dr6Flags, Dr7Flags, Dr7Value,
+
+use self::{breakpoint::Breakpoints, maps::MemoryMapping, window::Windows};
+use crate::syscall::{
+    CallSite, SyscallInfoDiscriminants, SyscallTime, TraceError,
+    capture::{CaptureLimits, CapturedBytes, MAX_BYTES_CSTRING},
+    inject::Tampering,
+    parse_error::{TraceErrEvt, TraceEvent},
 };
 
-use crate::syscall::{SyscallInfoDiscriminants, TraceError, parse_error::{TraceErrEvt, TraceEvent}};
+mod breakpoint;
+pub mod maps;
+pub mod window;
 
 #[derive(strum::EnumIs)]
 pub enum WaitEvents {
@@ -34,6 +45,10 @@ pub enum WaitEvents {
     Exec,
     Stopped(Signal),
     Clone,
+    /// Stop caused by `SECCOMP_RET_TRACE`, works the same way as syscall-entry stop
+    Seccomp,
+    /// Breakpoint at the address was hit, see [`Tracee::add_breakpoint`]
+    Breakpoint(u64),
 }
 
 #[derive(Default)]
@@ -57,15 +72,34 @@ impl SignalStorage {
 static CLOCK_TIME: LazyLock<f64> =
     LazyLock::new(|| unsafe { libc::sysconf(libc::_SC_CLK_TCK) as f64 });
 
-// TODO allow configuring this
-const MAX_BYTES_CSTRING: usize = 2048 * 1024; // 2 MiB
 const BUFFER_SIZE: usize = 128;
+/// Max number of strings read from a NULL-terminated array, like `argv`
+const MAX_STRING_ARRAY_LEN: usize = 0x10000;
 
 type ErrnoResult<T> = Result<T, Errno>;
 
 pub struct Tracee {
     pid: Pid,
     signal: SignalStorage,
+    record_realtime: bool,
+    seccomp: bool,
+    /// Monotonic time of the last stop, together with realtime if `record_realtime` is enabled
+    last_stop: (Duration, Option<Duration>),
+    syscall_time: SyscallTime,
+    capture_limits: CaptureLimits,
+    /// Bytes which can still be captured during the current syscall
+    capture_budget: usize,
+    /// The tracee executed a new program, which wasn't reported yet
+    exec_pending: bool,
+    /// Build-ids of modules mapped in the current program
+    build_ids: HashMap<String, Option<Vec<u8>>>,
+    stack_depth: usize,
+    breakpoints: Breakpoints,
+    windows: Windows,
+    /// Change of the result of the current syscall, applied at its exit
+    tampering: Option<Tampering>,
+    /// Event of the last applied `tampering`, which wasn't taken yet
+    tampered: Option<TraceEvent>,
 }
 
 impl Tracee {
@@ -76,30 +110,100 @@ impl Tracee {
         Tracee {
             pid,
             signal: SignalStorage::default(),
+            record_realtime: false,
+            seccomp: false,
+            last_stop: (Duration::ZERO, None),
+            syscall_time: SyscallTime::default(),
+            capture_limits: CaptureLimits::default(),
+            capture_budget: usize::MAX,
+            exec_pending: false,
+            build_ids: HashMap::new(),
+            stack_depth: 0,
+            breakpoints: Breakpoints::default(),
+            windows: Windows::default(),
+            tampering: None,
+            tampered: None,
         }
     }
 
-    // TODO add a wait_for_syscall_stop type of method here, apply this for SIGTRAP
+    /// Resume the tracee with `PTRACE_CONT` and rely on seccomp stops instead of stopping on every
+    /// syscall, see [`crate::seccomp`]
+    pub fn use_seccomp(&mut self, value: bool) {
+        self.seccomp = value;
+    }
+
+    /// Also record `CLOCK_REALTIME` on every syscall entry, so traces can be correlated with logs
+    pub fn record_realtime(&mut self, value: bool) {
+        self.record_realtime = value;
+    }
+
+    pub fn set_capture_limits(&mut self, limits: CaptureLimits) {
+        self.capture_limits = limits;
+        self.capture_budget = limits.max_per_syscall;
+    }
+
+    /// Returns `true` once after the tracee executed a new program
+    pub fn take_exec(&mut self) -> bool {
+        mem::take(&mut self.exec_pending)
+    }
+
+    /// Path of the program the tracee is currently running
+    pub fn executable(&self) -> io::Result<PathBuf> {
+        std::fs::read_link(format!("/proc/{}/exe", self.pid))
+    }
+
+    /// Target of file descriptor `fd` of the tracee, like a path or `socket:[1234]`
+    pub fn fd_target(&self, fd: u64) -> io::Result<PathBuf> {
+        std::fs::read_link(format!("/proc/{}/fd/{fd}", self.pid))
+    }
+
+    /// Max number of frames captured by [`Tracee::capture_stack`], 0 disables it
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.stack_depth = depth;
+    }
+
     pub fn wait_for_stop(&mut self) -> Result<WaitEvents, TraceErrEvt> {
-        match waitpid(self.pid, None) {
+        let status = if self.seccomp {
+            self.wait_for_tracee()
+        } else {
+            waitpid(self.pid, None)
+        };
+        self.mark_stop()?;
+        match status {
             Ok(WaitStatus::PtraceEvent(_, Signal::SIGTRAP, PTRACE_EVENT_EXEC)) => {
-                warn!("stopped on ptrace event exec");
+                debug!("stopped on ptrace event exec");
+                self.exec_pending = true;
+                self.build_ids.clear();
+                self.clear_breakpoints();
+                self.clear_windows();
                 Ok(WaitEvents::Exec)
             }
-            Ok(WaitStatus::PtraceEvent(_, Signal::SIGTRAP, PTRACE_EVENT_FORK)) => {
-                Ok(WaitEvents::Fork)
-            }
+            Ok(WaitStatus::PtraceEvent(
+                _,
+                Signal::SIGTRAP,
+                PTRACE_EVENT_FORK | PTRACE_EVENT_VFORK,
+            )) => Ok(WaitEvents::Fork),
             Ok(WaitStatus::PtraceEvent(_, Signal::SIGTRAP, PTRACE_EVENT_CLONE)) => {
                 Ok(WaitEvents::Clone)
             }
+            Ok(WaitStatus::PtraceEvent(_, Signal::SIGTRAP, PTRACE_EVENT_SECCOMP)) => {
+                Ok(WaitEvents::Seccomp)
+            }
             Ok(WaitStatus::PtraceSyscall(_)) => Ok(WaitEvents::Syscall),
+            Ok(WaitStatus::Stopped(_, Signal::SIGTRAP))
+                if let Some(address) = self.hit_breakpoint()? =>
+            {
+                trace!("tracee stopped on breakpoint at {address:#x}");
+                self.update_windows(address)?;
+                Ok(WaitEvents::Breakpoint(address))
+            }
             Ok(WaitStatus::Stopped(_, signal)) => {
                 trace!("tracee stopped on signal {signal:?}");
                 self.signal.store(signal);
                 // FIXME this is here to be able to run programs with fork in them
                 if signal == Signal::SIGCHLD {
                     trace!("continuing on SIGCHLD");
-                    self.syscall()?;
+                    self.resume()?;
                     return self.wait_for_stop();
                 }
                 Ok(WaitEvents::Stopped(signal))
@@ -120,12 +224,79 @@ impl Tracee {
         }
     }
 
+    /// Waits for the traced process while resuming all other attached processes
+    ///
+    /// Threads and children of the tracee inherit its seccomp filter and their syscalls would fail
+    /// with `ENOSYS` if they weren't traced, so they are attached, but never stopped for long.
+    fn wait_for_tracee(&self) -> nix::Result<WaitStatus> {
+        loop {
+            let status = waitpid(None, Some(WaitPidFlag::__WALL))?;
+            let Some(pid) = status.pid() else {
+                continue;
+            };
+            if pid == self.pid {
+                return Ok(status);
+            }
+            let signal = match status {
+                // SIGSTOP is the initial stop of newly attached process
+                WaitStatus::Stopped(_, Signal::SIGSTOP | Signal::SIGTRAP)
+                | WaitStatus::PtraceEvent(..)
+                | WaitStatus::PtraceSyscall(_) => None,
+                WaitStatus::Stopped(_, signal) => Some(signal),
+                _ => continue,
+            };
+            trace!("resuming attached process {pid}");
+            // it could have been killed in the meantime, that doesn't matter
+            let _ = ptrace::cont(pid, signal);
+        }
+    }
+
+    fn mark_stop(&mut self) -> ErrnoResult<()> {
+        let monotonic = clock_gettime(ClockId::CLOCK_MONOTONIC)?.into();
+        let realtime = if self.record_realtime {
+            Some(clock_gettime(ClockId::CLOCK_REALTIME)?.into())
+        } else {
+            None
+        };
+        self.last_stop = (monotonic, realtime);
+        Ok(())
+    }
+
+    /// Keeps the tracee stopped for `duration`
+    ///
+    /// The stop is marked again afterwards, so the time spent held isn't counted in the
+    /// [`SyscallTime`] of a syscall begun after it.
+    pub fn hold(&mut self, duration: Duration) -> ErrnoResult<()> {
+        trace!("holding tracee for {duration:?}");
+        std::thread::sleep(duration);
+        self.mark_stop()
+    }
+
+    /// Marks the last stop as the entry of a new syscall
+    pub fn begin_syscall(&mut self) {
+        let (entry, realtime_entry) = self.last_stop;
+        self.syscall_time = SyscallTime {
+            entry,
+            exit: entry,
+            realtime_entry,
+        };
+        self.capture_budget = self.capture_limits.max_per_syscall;
+    }
+
+    /// Timing of the current syscall, using the last stop as its exit
+    #[must_use]
+    pub fn syscall_time(&self) -> SyscallTime {
+        SyscallTime {
+            exit: self.last_stop.0,
+            ..self.syscall_time
+        }
+    }
+
+    /// Copies `len` bytes of tracee's memory at `base`, unmapped memory like a null `base` fails
+    /// with `EFAULT`
     pub fn memcpy(&self, base: u64, len: usize) -> ErrnoResult<Vec<u8>> {
-        let base = base.try_into().unwrap();
+        let base = base.try_into().map_err(|_| Errno::EFAULT)?;
         let mut data = vec![0; len];
-        if base == 0 {
-            return Ok(vec![0]);
-        }
         process_vm_readv(
             self.pid,
             &mut [IoSliceMut::new(&mut data)],
@@ -134,16 +305,39 @@ impl Tracee {
         Ok(data)
     }
 
-    #[must_use]
-    pub fn get_cpu_time(&self) -> f64 {
-        let stat_file = std::fs::read_to_string(format!("/proc/{}/stat", self.pid)).unwrap();
-        stat_file
+    /// Writes `data` into tracee's memory at `base`, returns the number of written bytes
+    ///
+    /// Unlike [`Tracee::write`], whole buffers are written at once, but only to writable
+    /// memory.
+    pub fn memwrite(&self, base: u64, data: &[u8]) -> ErrnoResult<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        let base = base.try_into().map_err(|_| Errno::EFAULT)?;
+        process_vm_writev(
+            self.pid,
+            &[IoSlice::new(data)],
+            &[RemoteIoVec {
+                base,
+                len: data.len(),
+            }],
+        )
+    }
+
+    /// User CPU time of the tracee in seconds
+    pub fn get_cpu_time(&self) -> Result<f64, TraceError> {
+        let stat_file = std::fs::read_to_string(format!("/proc/{}/stat", self.pid))
+            .map_err(|err| TraceError::ProcfsError(err.kind()))?;
+        // the command name in parentheses can contain spaces
+        let (_, fields) = stat_file
+            .rsplit_once(')')
+            .ok_or(TraceError::InvalidProcfs("stat"))?;
+        let utime = fields
             .split_whitespace()
-            .nth(13)
-            .unwrap()
-            .parse::<f64>()
-            .unwrap()
-            / *CLOCK_TIME
+            .nth(11)
+            .and_then(|utime| utime.parse::<f64>().ok())
+            .ok_or(TraceError::InvalidProcfs("stat"))?;
+        Ok(utime / *CLOCK_TIME)
     }
 
     pub fn memcpy_struct<T>(&self, base: u64) -> ErrnoResult<Option<T>>
@@ -154,11 +348,25 @@ impl Tracee {
             return Ok(None);
         }
         let bytes = self.memcpy(base, std::mem::size_of::<T>())?;
-        let (_, sock_addr, _) = unsafe { bytes.align_to::<T>() };
-        Ok(Some(sock_addr[0]))
+        // the copy is not aligned for T
+        Ok(Some(unsafe { bytes.as_ptr().cast::<T>().read_unaligned() }))
     }
 
     pub fn memcpy_until<T>(&self, base: u64, function: T) -> ErrnoResult<Vec<u8>>
+    where
+        T: Fn(&u8) -> bool,
+    {
+        let data = self.memcpy_until_max(base, MAX_BYTES_CSTRING, function)?;
+        if data.len() >= MAX_BYTES_CSTRING - 1 {
+            debug!("reached memcpy_until byte read limit");
+        }
+        Ok(data)
+    }
+
+    /// Like [`Self::memcpy_until`], but stops reading once at least `max` bytes were read
+    ///
+    /// The memory is read in chunks, so the returned data can be a bit longer than `max`.
+    fn memcpy_until_max<T>(&self, base: u64, max: usize, function: T) -> ErrnoResult<Vec<u8>>
     where
         T: Fn(&u8) -> bool,
     {
@@ -169,27 +377,30 @@ impl Tracee {
         let mut buf = [0; BUFFER_SIZE];
         let mut total_bytes_read = 0usize;
 
-        let base: usize = base.try_into().unwrap();
+        let base: usize = base.try_into().map_err(|_| Errno::EFAULT)?;
 
-        while data.len() < MAX_BYTES_CSTRING {
-            total_bytes_read += process_vm_readv(
+        while data.len() < max {
+            let read = process_vm_readv(
                 self.pid,
                 &mut [IoSliceMut::new(&mut buf)],
                 &[RemoteIoVec {
-                    base: base + total_bytes_read,
+                    base: base.checked_add(total_bytes_read).ok_or(Errno::EFAULT)?,
                     len: BUFFER_SIZE,
                 }],
             )?;
+            if read == 0 {
+                break;
+            }
+            total_bytes_read += read;
+            // the read stops early at the end of the mapping
+            let buf = &buf[..read];
 
             // if let Some(index) = buf.iter().position(|num| *num == 0) {
             if let Some(index) = buf.iter().position(&function) {
                 data.extend_from_slice(&buf[..=index]);
                 break;
             }
-            data.extend_from_slice(&buf);
-        }
-        if data.len() >= MAX_BYTES_CSTRING - 1 {
-            debug!("reached memcpy_until byte read limit");
+            data.extend_from_slice(buf);
         }
         Ok(data)
     }
@@ -198,6 +409,71 @@ impl Tracee {
         self.memcpy_until(base, |num| *num == 0)
     }
 
+    /// Takes at most `limit` bytes of `len` from the budget of the current syscall
+    fn take_capture_budget(&mut self, limit: usize, len: usize) -> usize {
+        let len = len.min(limit).min(self.capture_budget);
+        self.capture_budget -= len;
+        len
+    }
+
+    /// Copies a buffer of `len` bytes within the [`CaptureLimits`] of the tracee
+    pub fn capture(&mut self, base: u64, len: usize) -> ErrnoResult<CapturedBytes> {
+        let limit = self.take_capture_budget(self.capture_limits.max_buffer, len);
+        let data = if limit == 0 {
+            vec![]
+        } else {
+            self.memcpy(base, limit)?
+        };
+        Ok(CapturedBytes {
+            data,
+            original_len: len,
+            truncated: limit < len,
+            redacted: false,
+        })
+    }
+
+    /// Copies a C string within the [`CaptureLimits`] of the tracee
+    ///
+    /// Reading stops once the limit is reached, so `original_len` of a truncated string is only
+    /// a lower bound. Strings longer than [`MAX_BYTES_CSTRING`] are always truncated.
+    pub fn capture_string(&mut self, base: u64) -> ErrnoResult<CapturedBytes> {
+        let max = self
+            .capture_limits
+            .max_string
+            .min(self.capture_budget)
+            .min(MAX_BYTES_CSTRING);
+        // one more byte tells whether the string ends within the limit
+        let data = self.memcpy_until_max(base, max + 1, |num| *num == 0)?;
+        let complete = data.last() == Some(&0);
+        let limit = self.take_capture_budget(self.capture_limits.max_string, data.len());
+        let mut captured = CapturedBytes::new(data, limit);
+        captured.truncated |= !complete;
+        Ok(captured)
+    }
+
+    /// Copies a NULL-terminated array of C strings, like `argv` or `envp`, within the
+    /// [`CaptureLimits`] of the tracee
+    pub fn capture_string_array(&mut self, base: u64) -> ErrnoResult<Vec<CapturedBytes>> {
+        let mut strings = vec![];
+        if base == 0 {
+            return Ok(strings);
+        }
+        let pointer_size = mem::size_of::<u64>();
+        while strings.len() < MAX_STRING_ARRAY_LEN {
+            let address = base
+                .checked_add((strings.len() * pointer_size) as u64)
+                .ok_or(Errno::EFAULT)?;
+            let bytes = self.memcpy(address, pointer_size)?;
+            let pointer = u64::from_ne_bytes(bytes.try_into().map_err(|_| Errno::EFAULT)?);
+            if pointer == 0 {
+                return Ok(strings);
+            }
+            strings.push(self.capture_string(pointer)?);
+        }
+        debug!("reached string array length limit");
+        Ok(strings)
+    }
+
     pub fn read_rax(&self) -> Result<i64, TraceError> {
         Ok(ptrace::read_user(self.pid, (RAX * 8) as *mut c_void)?)
     }
@@ -232,7 +508,7 @@ impl Tracee {
     ) -> Result<i64, TraceErrEvt> {
         debug!("Parsing syscall return...");
         self.syscall()?;
-        self.wait_for_stop()?;
+        self.wait_for_syscall_stop()?;
         let syscall_info: PtraceSyscallInfo = self.syscall_info()?.into();
         let Some(PtraceSyscallInfoData::Exit {
             return_value,
@@ -242,51 +518,255 @@ impl Tracee {
             return Err(TraceErrEvt::Error(TraceError::InvalidSyscallInfo(syscall_info)));
         };
         if is_error {
+            self.tampering = None;
             return Err(TraceErrEvt::Event(TraceEvent::SyscallError {
                 syscall,
                 error: parse_syscall_error(return_value),
                 rip: syscall_info.instruction_pointer,
-                cpu_time: self.get_cpu_time()
+                cpu_time: self.get_cpu_time()?,
+                time: self.syscall_time(),
             }));
         }
         Ok(return_value)
     }
 
+    /// Changes the result of the current syscall at its exit, if it succeeds, replaces the
+    /// tampering set earlier
+    ///
+    /// Has to be set at the syscall entry and is applied by [`Tracee::apply_tampering`], the
+    /// change is recorded as [`TraceEvent::Tampered`], see [`Tracee::take_tampered`].
+    pub fn set_tampering(&mut self, tampering: Option<Tampering>) {
+        self.tampering = tampering;
+    }
+
+    /// Event of the last tampered syscall result, if it wasn't taken yet
+    pub fn take_tampered(&mut self) -> Option<TraceEvent> {
+        self.tampered.take()
+    }
+
+    /// Applies the tampering of the syscall the tracee is stopped on at its exit, failed
+    /// syscalls are not tampered
+    ///
+    /// It's applied after the result was recorded, so the record keeps what the kernel returned.
+    pub fn apply_tampering(&mut self) -> Result<(), TraceError> {
+        let Some(tampering) = self.tampering.take() else {
+            return Ok(());
+        };
+        let return_value = self.read_rax()?;
+        if (-4095..0).contains(&return_value) {
+            return Ok(());
+        }
+        let (tampered, event) = tampering.apply(self, return_value)?;
+        debug!("tampered syscall result {return_value} to {tampered}");
+        self.tampered = Some(event);
+        Ok(())
+    }
+
+    /// Sets the return value of the syscall the tracee is stopped on at its exit
+    pub fn set_return_value(&self, value: i64) -> ErrnoResult<()> {
+        let mut regs = self.getregs()?;
+        regs.rax = value.cast_unsigned();
+        ptrace::setregs(self.pid, regs)
+    }
+
+    /// Waits for the next syscall stop, other stops (e.g. exec or clone events which come before
+    /// syscall-exit stop) are skipped
+    ///
+    /// Signals received meanwhile are delivered to the tracee without calling
+    /// [`TraceHandler::on_signal`](crate::syscall::handler::TraceHandler::on_signal).
+    pub fn wait_for_syscall_stop(&mut self) -> Result<(), TraceErrEvt> {
+        while !self.wait_for_stop()?.is_syscall() {
+            self.syscall()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces arguments of the syscall the tracee is stopped on at its entry
+    pub fn set_args(&self, args: &[u64; 6]) -> ErrnoResult<()> {
+        let mut regs = self.getregs()?;
+        [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9] = *args;
+        ptrace::setregs(self.pid, regs)
+    }
+
+    /// Pid of the process or thread created by the fork or clone event the tracee is stopped on
+    pub fn new_child(&self) -> ErrnoResult<Pid> {
+        let pid = ptrace::getevent(self.pid)?;
+        Ok(Pid::from_raw(pid as libc::pid_t))
+    }
+
+    /// The signal the tracee stopped on isn't delivered to it when it's resumed
+    pub fn suppress_signal(&mut self) {
+        self.signal.get();
+    }
+
+    /// Stops tracing the stopped tracee, it continues untraced with its pending signal
+    ///
+    /// Breakpoints are removed first, the tracee can't be used afterwards.
+    pub fn detach(&mut self) -> ErrnoResult<()> {
+        self.remove_all_breakpoints()?;
+        ptrace::detach(self.pid, self.signal.get())
+    }
+
+    /// Makes the syscall the tracee is stopped on at its entry return `-error` without running it
+    pub fn inject_error(&mut self, error: Errno) -> Result<(), TraceErrEvt> {
+        self.emulate_syscall(-(error as i64), None)
+    }
+
+    /// Makes the syscall the tracee is stopped on at its entry return `return_value` without
+    /// running it, `output` is written to its address in tracee's memory like by the kernel
+    pub fn emulate_syscall(
+        &mut self,
+        return_value: i64,
+        output: Option<(u64, &[u8])>,
+    ) -> Result<(), TraceErrEvt> {
+        let mut regs = self.getregs()?;
+        // the kernel skips syscall -1
+        regs.orig_rax = u64::MAX;
+        ptrace::setregs(self.pid, regs)?;
+        self.resume_unrecorded()?;
+        if let Some((address, data)) = output {
+            self.memwrite(address, data)?;
+        }
+        self.set_return_value(return_value)?;
+        Ok(())
+    }
+
+    /// Lets the kernel run the syscall the tracee is stopped on and waits for its exit without
+    /// reading anything, the syscall isn't recorded
+    ///
+    /// Unlike [`Action::Skip`](crate::syscall::handler::Action::Skip), the syscall still runs.
+    pub fn resume_unrecorded(&mut self) -> Result<(), TraceErrEvt> {
+        self.syscall()?;
+        self.wait_for_syscall_stop()?;
+        self.apply_tampering()?;
+        Ok(())
+    }
+
     pub fn syscall(&mut self) -> ErrnoResult<()> {
+        self.step_over_breakpoint()?;
         ptrace::syscall(self.pid, self.signal.get())
     }
 
     pub fn cont(&mut self) -> ErrnoResult<()> {
+        self.step_over_breakpoint()?;
         ptrace::cont(self.pid, self.signal.get())
     }
 
-    fn translate_address(&self, mut func: impl FnMut((usize, usize), usize) -> Option<usize>) -> Result<Option<usize>, io::Error> {
-        let file = File::open(format!("/proc/{pid}/task/{pid}/maps", pid = self.pid))?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line?;
-            let mut it = line.split_whitespace();
-            let virt_addresses = it.next().unwrap();
+    /// Resumes the tracee until the next syscall which should be traced
+    pub fn resume(&mut self) -> ErrnoResult<()> {
+        // outside of trace windows only breakpoints have to stop the tracee
+        if self.seccomp || !self.is_tracing() {
+            self.cont()
+        } else {
+            self.syscall()
+        }
+    }
 
-            let (start, stop) = virt_addresses.split_once('-').unwrap();
-            let start = usize::from_str_radix(start, 16).unwrap();
-            let stop = usize::from_str_radix(stop, 16).unwrap();
+    /// Memory mappings of the tracee
+    pub fn mappings(&self) -> io::Result<Vec<MemoryMapping>> {
+        let content =
+            std::fs::read_to_string(format!("/proc/{pid}/task/{pid}/maps", pid = self.pid))?;
+        Ok(maps::parse_maps(&content))
+    }
 
-            // permissions
-            it.next();
-            let elf_offset = usize::from_str_radix(it.next().unwrap(), 16).unwrap();
+    /// Resolves `address` to the module it's mapped from
+    pub fn call_site(&mut self, address: u64) -> io::Result<CallSite> {
+        let mappings = self.mappings()?;
+        Ok(self.resolve_call_site(&mappings, address))
+    }
+
+    /// Resolves `address` using already read `mappings`
+    pub fn resolve_call_site(&mut self, mappings: &[MemoryMapping], address: u64) -> CallSite {
+        let Some(mapping) = mappings.iter().find(|mapping| mapping.contains(address)) else {
+            return CallSite {
+                address,
+                module: None,
+                build_id: None,
+                offset: address,
+            };
+        };
+        let build_id = match &mapping.pathname {
+            Some(pathname) if mapping.is_file() => self
+                .build_ids
+                .entry(pathname.clone())
+                .or_insert_with(|| maps::read_build_id(Path::new(pathname)))
+                .clone(),
+            _ => None,
+        };
+        CallSite {
+            address,
+            module: mapping.pathname.clone(),
+            build_id,
+            offset: mapping.offset_of(address),
+        }
+    }
 
-            let func_res = func((start, stop), elf_offset);
-            if func_res.is_some() {
-                return Ok(func_res)
+    /// Walks the frame pointers of the tracee stopped in a syscall and resolves return addresses
+    /// of its callers, the walk stops on the first invalid frame
+    ///
+    /// Syscall wrappers usually don't set up a frame of their own, so the return address to the
+    /// innermost caller is read from the top of the stack first.
+    pub fn capture_stack(&mut self, mappings: &[MemoryMapping]) -> ErrnoResult<Vec<CallSite>> {
+        let mut stack = vec![];
+        if self.stack_depth == 0 {
+            return Ok(stack);
+        }
+        let is_code = |address| {
+            mappings
+                .iter()
+                .any(|mapping| mapping.contains(address) && mapping.permissions.contains('x'))
+        };
+        let regs = self.getregs()?;
+        let innermost = self
+            .memcpy(regs.rsp, 8)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_ne_bytes)
+            .filter(|address| is_code(*address));
+        if let Some(address) = innermost {
+            stack.push(self.resolve_call_site(mappings, address));
+        }
+        let mut frame = regs.rbp;
+        let mut walked = 0;
+        while stack.len() < self.stack_depth && frame != 0 && frame % 8 == 0 {
+            // saved frame pointer of the caller followed by the return address
+            let Ok(bytes) = self.memcpy(frame, 16) else {
+                break;
+            };
+            let (saved_frame, return_address) = bytes.split_at(8);
+            let saved_frame = u64::from_ne_bytes(saved_frame.try_into().unwrap());
+            let return_address = u64::from_ne_bytes(return_address.try_into().unwrap());
+            if !is_code(return_address) {
+                break;
             }
+            // a wrapper with a frame of its own has the innermost return address in it
+            if walked > 0 || innermost != Some(return_address) {
+                stack.push(self.resolve_call_site(mappings, return_address));
+            }
+            walked += 1;
+            // the stack grows down, so callers' frames are always above
+            if saved_frame <= frame {
+                break;
+            }
+            frame = saved_frame;
         }
-        Ok(None)
+        Ok(stack)
+    }
 
+    fn translate_address(
+        &self,
+        func: impl FnMut(&MemoryMapping) -> Option<usize>,
+    ) -> Result<Option<usize>, io::Error> {
+        Ok(self.mappings()?.iter().find_map(func))
     }
 
     pub fn translate_address_to_virtual(&self, requested_addr: usize) -> Result<Option<usize>, io::Error> {
-        self.translate_address(|(start, stop), elf_offset| {
+        self.translate_address(|mapping| {
+            let (start, stop, elf_offset) = (
+                mapping.start as usize,
+                mapping.end as usize,
+                mapping.offset as usize,
+            );
             if requested_addr < elf_offset { return None; }
 
             let difference = stop - start;
@@ -298,7 +778,12 @@ impl Tracee {
     }
 
     pub fn translate_address_from_virtual(&self, requested_addr: usize) -> Result<Option<usize>, io::Error> {
-        self.translate_address(|(start, stop), elf_offset| {
+        self.translate_address(|mapping| {
+            let (start, stop, elf_offset) = (
+                mapping.start as usize,
+                mapping.end as usize,
+                mapping.offset as usize,
+            );
             let range = start..=stop;
             if range.contains(&requested_addr) {
                 Some(requested_addr - start + elf_offset)
@@ -308,39 +793,6 @@ impl Tracee {
         })
     }
 
-    // this isn't gonna be compatible outside of x86, just like many other code around here
-    pub fn add_local_breakpoint(&self, break_address: usize) -> ErrnoResult<()> {
-        // break_address gets written to Dr0
-        // let break_register = Dr0::write(break_address.try_into().unwrap());
-        let bits = self.read_user(debugreg_offset(7))?;
-        let mut dr7 = Dr7Value::from_bits(bits.cast_unsigned()).unwrap();
-        dr7.set_flags(Dr7Flags::LOCAL_BREAKPOINT_0_ENABLE, true);
-        dr7.set_condition(
-            DebugAddressRegisterNumber::Dr0,
-            BreakpointCondition::InstructionExecution,
-        );
-        dr7.set_size(DebugAddressRegisterNumber::Dr0, BreakpointSize::Length1B);
-
-        self.write_user(debugreg_offset(0), break_address as i64)?;
-        self.write_user(debugreg_offset(7), dr7.bits().cast_signed())?;
-        Ok(())
-    }
-
-    pub fn check_and_remove_break(&self) -> ErrnoResult<()> {
-        let dr6 = self.read_user(debugreg_offset(6))?;
-        let dr6 = Dr6Flags::from_bits_truncate(dr6.cast_unsigned());
-        if !dr6.contains(Dr6Flags::TRAP0) {
-            error!("Breakpoint was not triggered when it was expected to trigger!");
-        }
-
-        // disable breakpoint
-        let bits = self.read_user(debugreg_offset(7))?;
-        let mut dr7 = Dr7Value::from_bits(bits.cast_unsigned()).unwrap();
-        dr7.set_flags(Dr7Flags::LOCAL_BREAKPOINT_0_ENABLE, false);
-        self.write_user(debugreg_offset(7), dr7.bits().cast_signed())?;
-        Ok(())
-    }
-
     pub fn syscall_info(&self) -> ErrnoResult<libc::ptrace_syscall_info> {
         ptrace::syscall_info(self.pid)
     }
@@ -403,7 +855,11 @@ impl From<libc::ptrace_syscall_info> for PtraceSyscallInfo {
                 })
             }
             libc::PTRACE_SYSCALL_INFO_NONE => None,
-            _ => panic!("{} is invalid value for ptrace_syscall_info.op", value.op),
+            // reported as TraceError::InvalidSyscallInfo by the parser
+            op => {
+                warn!("{op} is invalid value for ptrace_syscall_info.op");
+                None
+            }
         };
         PtraceSyscallInfo {
             flags: value.flags,
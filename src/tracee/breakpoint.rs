@@ -0,0 +1,193 @@
+//! Breakpoints in the tracee, hardware ones in DR0–DR3 and software `int3` ones when all debug
+//! registers are used
+//!
+//! Only the traced thread is stopped by breakpoints. Debug registers are per thread, so other
+//! threads run past hardware breakpoints, but they would be killed by the `SIGTRAP` of `int3`.
+//! Software breakpoints are therefore refused once the tracee has more threads. Threads created
+//! after a software breakpoint was set are not protected.
+use std::{collections::HashMap, fs};
+
+use libc::c_long;
+use log::{trace, warn};
+use nix::sys::{
+    ptrace,
+    wait::{WaitStatus, waitpid},
+};
+use x86_64::registers::debug::{
+    BreakpointCondition, BreakpointSize, DebugAddressRegisterNumber, Dr6Flags, Dr7Flags, Dr7Value,
+};
+
+use super::{ErrnoResult, Tracee, debugreg_offset};
+use crate::syscall::parse_error::TraceError;
+
+const INT3: u8 = 0xCC;
+const DEBUG_ADDRESS_REGISTERS: usize = 4;
+
+#[derive(Debug, Default)]
+pub(super) struct Breakpoints {
+    /// Addresses in DR0–DR3
+    hardware: [Option<u64>; DEBUG_ADDRESS_REGISTERS],
+    /// Original bytes replaced by `int3`, keyed by address
+    software: HashMap<u64, u8>,
+    /// Software breakpoint the tracee is stopped on, it has to be stepped over before resuming
+    step_over: Option<u64>,
+}
+
+impl Breakpoints {
+    fn is_empty(&self) -> bool {
+        self.hardware.iter().all(Option::is_none) && self.software.is_empty()
+    }
+
+    fn contains(&self, address: u64) -> bool {
+        self.hardware.contains(&Some(address)) || self.software.contains_key(&address)
+    }
+}
+
+/// `DebugAddressRegisterNumber` of debug register `index` in 0..4
+fn register(index: usize) -> DebugAddressRegisterNumber {
+    #[expect(clippy::cast_possible_truncation, reason = "index is lower than 4")]
+    DebugAddressRegisterNumber::new(index as u8).expect("there are 4 debug address registers")
+}
+
+impl Tracee {
+    /// Stops the tracee when it executes the instruction at `address`
+    ///
+    /// Hit breakpoints are reported by [`Tracee::wait_for_stop`] as
+    /// [`WaitEvents::Breakpoint`](super::WaitEvents::Breakpoint) and stay set until removed. A
+    /// debug register is used if any is free, otherwise the instruction is replaced by `int3`.
+    pub fn add_breakpoint(&mut self, address: u64) -> Result<(), TraceError> {
+        if self.breakpoints.contains(address) {
+            return Ok(());
+        }
+        let Some(index) = self.breakpoints.hardware.iter().position(Option::is_none) else {
+            return self.add_software_breakpoint(address);
+        };
+        trace!("adding hardware breakpoint DR{index} at {address:#x}");
+        let register = register(index);
+        let bits = self.read_user(debugreg_offset(7))?;
+        let mut dr7 = Dr7Value::from_bits_truncate(bits.cast_unsigned());
+        dr7.set_flags(Dr7Flags::local_breakpoint_enable(register), true);
+        dr7.set_condition(register, BreakpointCondition::InstructionExecution);
+        dr7.set_size(register, BreakpointSize::Length1B);
+        self.write_user(debugreg_offset(index), address.cast_signed())?;
+        self.write_user(debugreg_offset(7), dr7.bits().cast_signed())?;
+        self.breakpoints.hardware[index] = Some(address);
+        Ok(())
+    }
+
+    /// Stops the tracee when it executes the instruction at `address` by replacing it with
+    /// `int3`, without using a debug register
+    ///
+    /// Fails with [`TraceError::BreakpointInThreads`] if the tracee has more than one thread.
+    pub fn add_software_breakpoint(&mut self, address: u64) -> Result<(), TraceError> {
+        if self.breakpoints.contains(address) {
+            return Ok(());
+        }
+        let threads = fs::read_dir(format!("/proc/{}/task", self.pid))
+            .map_err(|err| TraceError::ProcfsError(err.kind()))?
+            .count();
+        if threads > 1 {
+            return Err(TraceError::BreakpointInThreads(address));
+        }
+        trace!("adding software breakpoint at {address:#x}");
+        let original = self.replace_byte(address, INT3)?;
+        self.breakpoints.software.insert(address, original);
+        Ok(())
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u64) -> ErrnoResult<()> {
+        if let Some(index) = self
+            .breakpoints
+            .hardware
+            .iter()
+            .position(|breakpoint| *breakpoint == Some(address))
+        {
+            let bits = self.read_user(debugreg_offset(7))?;
+            let mut dr7 = Dr7Value::from_bits_truncate(bits.cast_unsigned());
+            dr7.set_flags(Dr7Flags::local_breakpoint_enable(register(index)), false);
+            self.write_user(debugreg_offset(7), dr7.bits().cast_signed())?;
+            self.breakpoints.hardware[index] = None;
+        } else if let Some(original) = self.breakpoints.software.remove(&address) {
+            self.replace_byte(address, original)?;
+        }
+        Ok(())
+    }
+
+    /// Removes all breakpoints from the tracee, e.g. before detaching from it
+    pub(super) fn remove_all_breakpoints(&mut self) -> ErrnoResult<()> {
+        let addresses = (self.breakpoints.hardware.iter().flatten())
+            .chain(self.breakpoints.software.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        for address in addresses {
+            self.remove_breakpoint(address)?;
+        }
+        self.breakpoints.step_over = None;
+        Ok(())
+    }
+
+    /// Forgets all breakpoints without touching the tracee, they don't survive an exec
+    pub(super) fn clear_breakpoints(&mut self) {
+        self.breakpoints = Breakpoints::default();
+    }
+
+    /// Address of the breakpoint which caused the current `SIGTRAP` stop, if any
+    ///
+    /// After a software breakpoint the instruction pointer is moved back to the replaced
+    /// instruction, so it gets executed once the tracee is resumed.
+    pub(super) fn hit_breakpoint(&mut self) -> ErrnoResult<Option<u64>> {
+        if self.breakpoints.is_empty() {
+            return Ok(None);
+        }
+        let dr6 = Dr6Flags::from_bits_truncate(self.read_user(debugreg_offset(6))?.cast_unsigned());
+        for (index, address) in self.breakpoints.hardware.into_iter().enumerate() {
+            if let Some(address) = address
+                && dr6.contains(Dr6Flags::trap(register(index)))
+            {
+                // the CPU never clears DR6, later breakpoints would look triggered
+                self.write_user(debugreg_offset(6), 0)?;
+                return Ok(Some(address));
+            }
+        }
+        let mut regs = self.getregs()?;
+        let address = regs.rip.wrapping_sub(1);
+        if !self.breakpoints.software.contains_key(&address) {
+            return Ok(None);
+        }
+        regs.rip = address;
+        ptrace::setregs(self.pid, regs)?;
+        self.breakpoints.step_over = Some(address);
+        Ok(Some(address))
+    }
+
+    /// Executes the original instruction of the software breakpoint the tracee is stopped on
+    /// and puts the breakpoint back
+    pub(super) fn step_over_breakpoint(&mut self) -> ErrnoResult<()> {
+        let Some(address) = self.breakpoints.step_over.take() else {
+            return Ok(());
+        };
+        let Some(&original) = self.breakpoints.software.get(&address) else {
+            // removed in the meantime
+            return Ok(());
+        };
+        self.replace_byte(address, original)?;
+        ptrace::step(self.pid, None)?;
+        match waitpid(self.pid, None)? {
+            // delivered with the next resume
+            WaitStatus::Stopped(_, signal) => self.signal.store(signal),
+            status => warn!("unexpected status {status:?} while stepping over a breakpoint"),
+        }
+        self.replace_byte(address, INT3)?;
+        Ok(())
+    }
+
+    /// Writes a single byte into tracee's code and returns the replaced one
+    fn replace_byte(&self, address: u64, byte: u8) -> ErrnoResult<u8> {
+        let word = self.read(address as usize)?;
+        let mut bytes = word.to_ne_bytes();
+        let original = bytes[0];
+        bytes[0] = byte;
+        self.write(address as usize, c_long::from_ne_bytes(bytes))?;
+        Ok(original)
+    }
+}
@@ -0,0 +1,163 @@
+//! Parsing of `/proc/<pid>/maps` and resolving addresses to the mapped modules
+use std::{fs::File, path::Path};
+
+use elf::{
+    ElfBytes, ElfStream,
+    abi::{PT_LOAD, PT_NOTE, STT_FUNC},
+    endian::AnyEndian,
+    note::Note,
+};
+
+/// Single line of `/proc/<pid>/maps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMapping {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    /// Offset of the mapping in the mapped file
+    pub offset: u64,
+    /// Path of the mapped file, or a pseudo-path like `[vdso]` or `[heap]`, `None` for
+    /// anonymous mappings
+    pub pathname: Option<String>,
+}
+
+impl MemoryMapping {
+    /// Parses a line of `/proc/<pid>/maps`, like
+    /// `7f0c1c200000-7f0c1c228000 r--p 00000000 00:1f 1234 /usr/lib/libc.so.6`
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line;
+        let (start, end) = next_field(&mut rest)?.split_once('-')?;
+        let permissions = next_field(&mut rest)?.to_owned();
+        let offset = next_field(&mut rest)?;
+        // device and inode
+        next_field(&mut rest)?;
+        next_field(&mut rest)?;
+        // the path can contain spaces, it's the rest of the line
+        let pathname = Some(rest.trim()).filter(|pathname| !pathname.is_empty());
+        Some(Self {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            permissions,
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            pathname: pathname.map(str::to_owned),
+        })
+    }
+
+    #[must_use]
+    pub fn contains(&self, address: u64) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+
+    /// Mapping is backed by a file, not anonymous memory or a pseudo-path like `[vdso]`
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        self.pathname
+            .as_ref()
+            .is_some_and(|pathname| pathname.starts_with('/'))
+    }
+
+    /// Offset of `address` in the mapped file, or from the start of the mapping if it's not a
+    /// file
+    #[must_use]
+    pub fn offset_of(&self, address: u64) -> u64 {
+        let offset = address.saturating_sub(self.start);
+        if self.is_file() {
+            self.offset + offset
+        } else {
+            offset
+        }
+    }
+
+    /// Address where `file_offset` of the mapped file is mapped, the inverse of
+    /// [`MemoryMapping::offset_of`]
+    #[must_use]
+    pub fn address_of(&self, file_offset: u64) -> Option<u64> {
+        let offset = file_offset.checked_sub(self.offset)?;
+        (self.is_file() && offset < self.end - self.start).then_some(self.start + offset)
+    }
+}
+
+fn next_field<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let trimmed = rest.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (field, remainder) = trimmed.split_at(end);
+    *rest = remainder;
+    Some(field).filter(|field| !field.is_empty())
+}
+
+/// Parses the whole content of `/proc/<pid>/maps`, invalid lines are skipped
+#[must_use]
+pub fn parse_maps(content: &str) -> Vec<MemoryMapping> {
+    content.lines().filter_map(MemoryMapping::parse).collect()
+}
+
+/// Returns the GNU build-id of an ELF file, if it has one
+#[must_use]
+pub fn read_build_id(path: &Path) -> Option<Vec<u8>> {
+    let file = File::open(path).ok()?;
+    let mut elf = ElfStream::<AnyEndian, _>::open_stream(file).ok()?;
+    let notes: Vec<_> = elf
+        .segments()
+        .iter()
+        .filter(|phdr| phdr.p_type == PT_NOTE)
+        .copied()
+        .collect();
+    for phdr in notes {
+        let Ok(notes) = elf.segment_data_as_notes(&phdr) else {
+            continue;
+        };
+        for note in notes {
+            if let Note::GnuBuildId(build_id) = note {
+                return Some(build_id.0.to_vec());
+            }
+        }
+    }
+    None
+}
+
+/// Converts virtual address of an ELF file to the offset in the file, using its loaded segments
+#[must_use]
+pub fn file_offset(elf: &ElfBytes<'_, AnyEndian>, address: u64) -> Option<u64> {
+    elf.segments()
+        .into_iter()
+        .flatten()
+        .filter(|phdr| phdr.p_type == PT_LOAD)
+        .find(|phdr| (phdr.p_vaddr..phdr.p_vaddr + phdr.p_filesz).contains(&address))
+        .map(|phdr| address - phdr.p_vaddr + phdr.p_offset)
+}
+
+/// Virtual address of function `name` from `.symtab`, or `.dynsym` if it's not there
+#[must_use]
+pub fn find_function(elf: &ElfBytes<'_, AnyEndian>, name: &str) -> Option<u64> {
+    let tables = [elf.symbol_table(), elf.dynamic_symbol_table()];
+    tables
+        .into_iter()
+        .filter_map(|table| table.ok().flatten())
+        .find_map(|(symbols, strings)| {
+            symbols.iter().find_map(|symbol| {
+                let found = symbol.st_symtype() == STT_FUNC
+                    && symbol.st_value != 0
+                    && strings
+                        .get(symbol.st_name as usize)
+                        .is_ok_and(|symbol| symbol == name);
+                found.then_some(symbol.st_value)
+            })
+        })
+}
+
+/// Address where virtual address `address` of the ELF file mapped from `path` is loaded in the
+/// tracee, the load bias of position independent files is taken from their mappings
+#[must_use]
+pub fn runtime_address(
+    mappings: &[MemoryMapping],
+    path: &str,
+    elf: &ElfBytes<'_, AnyEndian>,
+    address: u64,
+) -> Option<u64> {
+    let offset = file_offset(elf, address)?;
+    mappings
+        .iter()
+        .filter(|mapping| mapping.pathname.as_deref() == Some(path))
+        .find_map(|mapping| mapping.address_of(offset))
+}
@@ -0,0 +1,120 @@
+//! Tracing only the syscalls made inside a function or between two addresses
+use log::{debug, trace};
+
+use super::Tracee;
+use crate::syscall::parse_error::TraceError;
+
+/// Part of the program in which syscalls are traced, see
+/// [`SyscallIterOpts::window`](crate::syscall::SyscallIterOpts::window)
+///
+/// Windows only apply to the program which is running when they are set, nothing is traced
+/// after the tracee executes another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceWindow {
+    /// From the call of the function until it returns, the function is looked up in `.symtab`
+    /// or `.dynsym` of the loaded modules
+    Function(String),
+    /// From executing `start` until executing `end`, both are virtual addresses from the ELF file
+    /// of the executable
+    Addresses { start: u64, end: u64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    start: u64,
+    /// `None` for functions, the end is their return address
+    end: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveWindow {
+    end: u64,
+    /// Stack pointer right after the function returns, it tells apart returns of recursive calls
+    stack_pointer: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Windows {
+    windows: Vec<Window>,
+    active: Option<ActiveWindow>,
+    /// Windows were set, so syscalls outside of them are not traced
+    enabled: bool,
+}
+
+impl Tracee {
+    /// Traces syscalls only after the tracee executes `start`, until it executes `end`
+    ///
+    /// Without `end`, `start` has to be the first instruction of a function and the window ends
+    /// when the function returns. Addresses are addresses in the tracee.
+    pub fn add_window(&mut self, start: u64, end: Option<u64>) -> Result<(), TraceError> {
+        self.add_breakpoint(start)?;
+        self.windows.windows.push(Window { start, end });
+        self.windows.enabled = true;
+        Ok(())
+    }
+
+    /// Syscalls are traced, because there are no windows or the tracee is inside one
+    #[must_use]
+    pub fn is_tracing(&self) -> bool {
+        !self.windows.enabled || self.windows.active.is_some()
+    }
+
+    /// Windows can't be used after exec, their addresses belong to the previous program
+    pub(super) fn clear_windows(&mut self) {
+        self.windows.windows.clear();
+        self.windows.active = None;
+    }
+
+    /// Enters or leaves a window after a breakpoint at `address` was hit
+    pub(super) fn update_windows(&mut self, address: u64) -> Result<(), TraceError> {
+        if let Some(active) = self.windows.active {
+            if address != active.end {
+                return Ok(());
+            }
+            if let Some(stack_pointer) = active.stack_pointer
+                && self.getregs()?.rsp != stack_pointer
+            {
+                trace!("return of a recursive call at {address:#x}");
+                return Ok(());
+            }
+            debug!("leaving trace window at {address:#x}");
+            self.windows.active = None;
+            if !self
+                .windows
+                .windows
+                .iter()
+                .any(|window| window.start == address)
+            {
+                self.remove_breakpoint(address)?;
+            }
+            return Ok(());
+        }
+        let Some(window) = self
+            .windows
+            .windows
+            .iter()
+            .find(|window| window.start == address)
+            .copied()
+        else {
+            return Ok(());
+        };
+        debug!("entering trace window at {address:#x}");
+        let active = if let Some(end) = window.end {
+            ActiveWindow {
+                end,
+                stack_pointer: None,
+            }
+        } else {
+            // the return address is on top of the stack at the first instruction
+            let stack_pointer = self.getregs()?.rsp;
+            let bytes = self.memcpy(stack_pointer, 8)?;
+            ActiveWindow {
+                end: u64::from_ne_bytes(bytes.try_into().expect("8 bytes were read")),
+                stack_pointer: Some(stack_pointer + 8),
+            }
+        };
+        self.add_breakpoint(active.end)?;
+        self.windows.active = Some(active);
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Spawning and tracing a program in one step
+//!
+//! [`Tracer`] spawns a [`Command`] as a tracee, with a seccomp filter if
+//! [`SyscallIterOpts::seccomp`] is enabled, and returns a [`TracedChild`] owning the child
+//! process, its trace and its [`TraceMetadata`].
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
+};
+
+use log::debug;
+use nix::unistd::Pid;
+use spawn_ptrace::CommandPtraceSpawn;
+use thiserror::Error;
+
+use crate::{
+    seccomp,
+    syscall::{
+        SyscallIter, SyscallIterError, SyscallIterOpts, TraceData,
+        handler::TraceHandler,
+        parse_error::{TraceError, TraceEvent},
+    },
+    trace_file::TraceMetadata,
+    tracee::Tracee,
+};
+
+#[derive(Error, Debug)]
+pub enum SpawnError {
+    #[error("could not spawn the command: {0}")]
+    Spawn(#[from] io::Error),
+    #[error("could not start tracing the command: {0}")]
+    Trace(#[from] SyscallIterError),
+}
+
+/// Builder of a traced child process
+pub struct Tracer {
+    command: Command,
+    opts: SyscallIterOpts,
+}
+
+impl Tracer {
+    /// Traces `command` with the default [`SyscallIterOpts`]
+    #[must_use]
+    pub fn command(command: Command) -> Self {
+        Self {
+            command,
+            opts: SyscallIterOpts::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn options(mut self, opts: SyscallIterOpts) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    #[must_use]
+    pub fn stdin(mut self, stdin: impl Into<Stdio>) -> Self {
+        self.command.stdin(stdin);
+        self
+    }
+
+    #[must_use]
+    pub fn stdout(mut self, stdout: impl Into<Stdio>) -> Self {
+        self.command.stdout(stdout);
+        self
+    }
+
+    #[must_use]
+    pub fn stderr(mut self, stderr: impl Into<Stdio>) -> Self {
+        self.command.stderr(stderr);
+        self
+    }
+
+    /// Sets an environment variable of the child, overriding the inherited one
+    #[must_use]
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.command.env(key, value);
+        self
+    }
+
+    /// Doesn't pass the environment variable `key` to the child
+    #[must_use]
+    pub fn env_remove(mut self, key: impl AsRef<OsStr>) -> Self {
+        self.command.env_remove(key);
+        self
+    }
+
+    /// Doesn't pass any inherited environment variables to the child
+    #[must_use]
+    pub fn env_clear(mut self) -> Self {
+        self.command.env_clear();
+        self
+    }
+
+    #[must_use]
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    /// Spawns the command stopped after its `execve` and starts tracing it
+    ///
+    /// The child is killed if tracing can't be started.
+    pub fn spawn(mut self) -> Result<TracedChild, SpawnError> {
+        let mut child = if self.opts.seccomp {
+            seccomp::spawn_ptrace(&mut self.command, &self.opts.filter)?
+        } else {
+            self.command.spawn_ptrace()?
+        };
+        let pid = Pid::from_raw(child.id().cast_signed());
+        debug!("traced pid: {pid}");
+        let metadata = TraceMetadata::new(pid, &self.command, &self.opts);
+        match SyscallIter::new(Tracee::new(pid), &self.opts) {
+            Ok(iter) => Ok(TracedChild {
+                child,
+                iter,
+                status: None,
+                metadata,
+            }),
+            Err(err) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Child process spawned by [`Tracer::spawn`], iterating over it returns its trace
+///
+/// The trace ends with [`TraceEvent::ProcessExit`] or [`TraceEvent::Terminated`] once the child
+/// exits, its status is then available from [`TracedChild::exit_status`].
+pub struct TracedChild {
+    child: Child,
+    iter: SyscallIter,
+    status: Option<ExitStatus>,
+    metadata: TraceMetadata,
+}
+
+impl TracedChild {
+    /// Process id of the child
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Handle of the standard input redirected to [`Stdio::piped`], `None` after the first call
+    pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+        self.child.stdin.take()
+    }
+
+    /// Handle of the standard output redirected to [`Stdio::piped`], `None` after the first call
+    pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.child.stdout.take()
+    }
+
+    /// Handle of the standard error redirected to [`Stdio::piped`], `None` after the first call
+    pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+        self.child.stderr.take()
+    }
+
+    /// Kills the child, the trace then ends with [`TraceEvent::Terminated`]
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.child.kill()
+    }
+
+    /// Returns the next syscall or event, see [`SyscallIter::next_with`]
+    pub fn next_with(
+        &mut self,
+        handler: &mut dyn TraceHandler,
+    ) -> Option<Result<TraceData, TraceError>> {
+        let data = self.iter.next_with(handler);
+        if let Some(Ok(TraceData::Event(event))) = &data
+            && let Some(status) = exit_status(event)
+        {
+            self.status = Some(status);
+        }
+        data
+    }
+
+    /// Exit status of the child, `None` until the end of its trace
+    #[must_use]
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        self.status
+    }
+
+    /// Description of the child and its tracing, with the exit status once the trace ended
+    #[must_use]
+    pub fn metadata(&self) -> TraceMetadata {
+        TraceMetadata {
+            exit_status: self.status.map(ExitStatus::into_raw),
+            ..self.metadata.clone()
+        }
+    }
+
+    /// Traces the child until it exits and returns its exit status
+    ///
+    /// The remaining trace is discarded. A child which was detached is waited for untraced.
+    pub fn wait(mut self) -> io::Result<ExitStatus> {
+        self.by_ref().for_each(drop);
+        match self.status {
+            Some(status) => Ok(status),
+            None => self.child.wait(),
+        }
+    }
+}
+
+impl Iterator for TracedChild {
+    type Item = Result<TraceData, TraceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(&mut ())
+    }
+}
+
+/// Converts an event ending the trace to the status returned by `waitpid`
+fn exit_status(event: &TraceEvent) -> Option<ExitStatus> {
+    let status = match *event {
+        TraceEvent::ProcessExit(code) => (code & 0xff) << 8,
+        TraceEvent::Terminated {
+            signal,
+            core_dumped,
+        } => signal as i32 | if core_dumped { 0x80 } else { 0 },
+        _ => return None,
+    };
+    Some(ExitStatus::from_raw(status))
+}
@@ -0,0 +1,13 @@
+#include <fcntl.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+int main(int argc, char** argv) {
+    char* new_argv[] = {"true", "--flag", "two words", NULL};
+    char* new_envp[] = {"FOO=bar", "EMPTY=", NULL};
+    if (argc > 1) {
+        syscall(SYS_execveat, AT_FDCWD, "/bin/true", new_argv, new_envp, 0);
+    } else {
+        execve("/bin/true", new_argv, new_envp);
+    }
+}
@@ -0,0 +1,64 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+// calls decoded syscalls with pseudo-random arguments, seeded by the first argument
+static uint64_t state;
+
+static uint64_t next(void) {
+    state = state * 6364136223846793005ULL + 1442695040888963407ULL;
+    return state >> 11;
+}
+
+// pointers which are never mapped, so the kernel doesn't write anywhere
+static uint64_t bad_pointer(void) {
+    switch (next() % 4) {
+    case 0:
+        return 0;
+    case 1:
+        return next() % 0x1000;
+    case 2:
+        return 0xffff800000000000ULL | next();
+    default:
+        return UINT64_MAX - next() % 0x1000;
+    }
+}
+
+int main(int argc, char **argv) {
+    state = argc > 1 ? strtoull(argv[1], NULL, 10) : 1;
+    char buf[64];
+    struct timespec time;
+    for (int i = 0; i < 100; i++) {
+        // /dev/null ignores unknown flags and the mode
+        long fd = syscall(SYS_openat, AT_FDCWD, "/dev/null", next(), next());
+        if (fd >= 0) {
+            syscall(SYS_read, fd, buf, next() % sizeof buf);
+            close(fd);
+        }
+        syscall(SYS_openat, next(), bad_pointer(), next(), next());
+        fd = syscall(SYS_socket, next() % 48, next() % 12, next() % 4);
+        if (fd >= 0) {
+            close(fd);
+        }
+        syscall(SYS_read, 1000 + next() % 1000, bad_pointer(), next() % 4096);
+        syscall(SYS_write, 1000 + next() % 1000, bad_pointer(), next() % 4096);
+        syscall(SYS_recvfrom, 1000 + next() % 1000, bad_pointer(), next(), next(), 0, 0);
+        syscall(SYS_bind, 1000 + next() % 1000, bad_pointer(), next());
+        syscall(SYS_accept, 1000 + next() % 1000, bad_pointer(), bad_pointer());
+        syscall(SYS_listen, 1000 + next() % 1000, next());
+        syscall(SYS_getrandom, buf, next() % sizeof buf, next() % 8);
+        syscall(SYS_getrandom, bad_pointer(), next() % 4096, next());
+        syscall(SYS_clock_gettime, next() % 16, &time);
+        syscall(SYS_clock_gettime, next(), bad_pointer());
+        syscall(SYS_unlink, bad_pointer());
+        syscall(SYS_execve, bad_pointer(), bad_pointer(), bad_pointer());
+        // undecoded syscalls with arguments decoded by the syscall table
+        syscall(SYS_newfstatat, next(), bad_pointer(), bad_pointer(), next());
+        syscall(SYS_readlinkat, next(), bad_pointer(), bad_pointer(), next());
+    }
+    return 0;
+}
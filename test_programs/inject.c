@@ -0,0 +1,24 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+// the exit code tells which calls failed as expected
+int main() {
+    int code = 0;
+    for (int i = 0; i < 3; i++) {
+        int fd = open("./inject.exec", O_RDONLY);
+        if (fd < 0 && errno == ENOENT) {
+            code += 10;
+        } else {
+            close(fd);
+        }
+    }
+    int fds[2];
+    socketpair(AF_UNIX, SOCK_STREAM, 0, fds);
+    if (write(fds[0], "data", 4) < 0 && errno == EAGAIN) {
+        code += 1;
+    }
+    write(1, "done\n", 5);
+    return code;
+}
@@ -0,0 +1,5 @@
+#include <unistd.h>
+
+int main() {
+    write(1, "main\n", 5);
+}
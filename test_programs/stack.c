@@ -0,0 +1,17 @@
+#include <unistd.h>
+
+#define FRAME __attribute__((noinline, optimize("no-omit-frame-pointer")))
+
+FRAME void inner(void) {
+    write(1, "stack\n", 6);
+    __asm__ volatile("" ::: "memory");
+}
+
+FRAME void outer(void) {
+    inner();
+    __asm__ volatile("" ::: "memory");
+}
+
+int main() {
+    outer();
+}
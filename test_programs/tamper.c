@@ -0,0 +1,26 @@
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+
+// the exit code tells which results were tampered as expected
+int main() {
+    int code = 0;
+    if (getpid() == 42) {
+        code += 1;
+    }
+    char buf[16] = {0};
+    int fd = open("./tamper.exec", O_RDONLY);
+    ssize_t n = read(fd, buf, sizeof buf);
+    if (n == 2) {
+        code += 10;
+    }
+    if (n == 5 && memcmp(buf, "fixed", 5) == 0) {
+        code += 20;
+    }
+    // the fixture is exhausted
+    if (read(fd, buf, sizeof buf) == 0) {
+        code += 40;
+    }
+    close(fd);
+    return code;
+}
@@ -0,0 +1,37 @@
+#include <unistd.h>
+
+#define HANDLER(name)                                                                              \
+    __attribute__((noinline)) void name(void) {                                                    \
+        getppid();                                                                                 \
+        __asm__ volatile("" ::: "memory");                                                         \
+    }
+
+HANDLER(handler_a)
+HANDLER(handler_b)
+HANDLER(handler_c)
+HANDLER(handler_d)
+HANDLER(handler_e)
+
+// kept as real recursion, not turned into a loop
+__attribute__((noinline, optimize("O0"))) int recurse(int depth) {
+    getppid();
+    if (depth == 0) {
+        return 0;
+    }
+    return recurse(depth - 1) + 1;
+}
+
+int main() {
+    for (int i = 0; i < 3; i++) {
+        getpid();
+        handler_a();
+    }
+    getpid();
+    handler_b();
+    handler_c();
+    handler_d();
+    handler_e();
+    getpid();
+    recurse(2);
+    getpid();
+}
@@ -0,0 +1,33 @@
+#include <pthread.h>
+#include <unistd.h>
+
+#define HANDLER(name)                                                                              \
+    __attribute__((noinline)) void name(void) {                                                    \
+        getppid();                                                                                 \
+        __asm__ volatile("" ::: "memory");                                                         \
+    }
+
+HANDLER(handler_a)
+HANDLER(handler_b)
+HANDLER(handler_c)
+HANDLER(handler_d)
+
+int fds[2];
+
+// keeps the process multithreaded until main exits
+void* wait_for_main(void* arg) {
+    char byte;
+    read(fds[0], &byte, 1);
+    return arg;
+}
+
+int main() {
+    pipe(fds);
+    pthread_t thread;
+    pthread_create(&thread, NULL, wait_for_main, NULL);
+    handler_a();
+    handler_b();
+    handler_c();
+    handler_d();
+    // exiting ends the thread, joining it would only sometimes wait on a futex
+}
@@ -1,14 +1,24 @@
 #[cfg(test)]
 mod tests {
-    use std::{process::Command, sync::Once};
+    use std::{
+        io,
+        process::{Command, Stdio},
+        sync::Once,
+    };
 
     use boubo_trace::{
-        syscall::{SyscallIter, SyscallIterOpts},
-        tracee::Tracee,
+        seccomp::{SeccompProgram, UntraceableSyscall},
+        symbolize::Symbolizer,
+        syscall::{
+            Syscall, SyscallInfo, SyscallInfoDiscriminants, SyscallIterOpts, TraceData,
+            capture::{CaptureLimits, CapturedBytes},
+            filter::SyscallFilter,
+            parse_error::TraceEvent,
+        },
+        tracer::{SpawnError, Tracer},
     };
     use insta::glob;
-    use nix::unistd::Pid;
-    use spawn_ptrace::CommandPtraceSpawn;
+    use nix::errno::Errno;
     static INIT: Once = Once::new();
 
     fn initialize() {
@@ -27,16 +37,441 @@ mod tests {
         initialize();
         glob!("../test_programs/build/", "*.exec", |exec| {
             println!("path: {}", exec.display());
-            let mut cmd = Command::new(exec)
+            // its trace is thousands of random syscalls, tests/fuzz.rs checks it
+            if exec.ends_with("fuzz.exec") {
+                return;
+            }
+            // the dynamic loader would search the library path set by cargo
+            let child = Tracer::command(Command::new(exec))
+                .env_clear()
                 .current_dir(exec.parent().unwrap())
-                .spawn_ptrace()
+                .spawn()
                 .unwrap();
-            let pid = Pid::from_raw(cmd.id().cast_signed());
-
-            let it = SyscallIter::new(Tracee::new(pid), &SyscallIterOpts::default());
-            let called_syscalls = it.unwrap().collect::<Result<Vec<_>, _>>().unwrap();
-            insta::assert_debug_snapshot!(called_syscalls);
-            cmd.wait().unwrap();
+            let called_syscalls = child.collect::<Result<Vec<_>, _>>().unwrap();
+            let mut settings = insta::Settings::clone_current();
+            for (regex, replacement) in UNSTABLE_FIELDS {
+                settings.add_filter(regex, *replacement);
+            }
+            if exec.ends_with("replay.exec") {
+                // it prints the random bytes and the time it got
+                settings.add_filter(
+                    r"(Write \{\s*fd: 1,\s*to_write: CapturedBytes \{\s*data: )\[[^\]]*\]",
+                    "$1[output]",
+                );
+            }
+            settings.bind(|| insta::assert_debug_snapshot!(called_syscalls));
         });
     }
+
+    /// Filters of the values which change between runs: timings, addresses, process ids and what
+    /// the programs got from the system
+    ///
+    /// Each filter matches a single field. Fields of a record are indented by 8 or more spaces,
+    /// so `(\n {8,}[^\n]*)*?` never leaves the record it started in.
+    const UNSTABLE_FIELDS: &[(&str, &str)] = &[
+        (r"cpu_time: [\d.]+", "cpu_time: [cpu_time]"),
+        (r"time: SyscallTime \{[^}]*\}", "time: [time]"),
+        (r"call_site: CallSite \{[^}]*\}", "call_site: [call_site]"),
+        (r"stack: \[(?:[^\[\]]|\[[^\[\]]*\])+\]", "stack: [stack]"),
+        (r"rip: \d+", "rip: [address]"),
+        // raw arguments of undecoded syscalls are mostly pointers
+        (r"(Unknown \{\s*id: \d+,\s*args: )\[[^\]]*\]", "$1[args]"),
+        (r"(Pointer\(\s*)\d+", "$1[address]"),
+        // mmap, brk and mremap return addresses, getpid, fork, getppid, gettid,
+        // set_tid_address and clone3 return process ids
+        (
+            r"(\bid: (?:9|12|25|39|57|110|186|218|435),(?:\n {8,}[^\n]*)*?\n {8,}return_value: )\d+",
+            "$1[return_value]",
+        ),
+        (r"\b(stack|parent_tid|child_tid|tls): \d+", "$1: [address]"),
+        (
+            r"(ClockGettime \{\s*clock_id: \d+,\s*time: Some\(\s*)[\d.]+s",
+            "$1[clock]",
+        ),
+        (
+            r"(random_bytes: CapturedBytes \{\s*data: )\[[^\]]*\]",
+            "$1[random]",
+        ),
+        // absolute path of the checkout
+        (r"(Exec \{\s*executable: )\[[^\]]*\]", "$1[path]"),
+        // port of the client, the first two bytes of its address
+        (
+            r"(Accept \{\s*sockfd: \d+,\s*addr: Some\(\s*sockaddr \{\s*sa_family: \d+,\s*sa_data: \[)\s*-?\d+,\s*-?\d+,",
+            "$1 [port],",
+        ),
+    ];
+
+    #[test]
+    fn syscall_timestamps() {
+        initialize();
+        let opts = SyscallIterOpts::default().realtime_timestamps(true);
+        let child = Tracer::command(Command::new("./read_limit.exec"))
+            .current_dir("test_programs/build/")
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let mut last_entry = None;
+        for call in child {
+            let TraceData::Syscall(syscall) = call.unwrap() else {
+                continue;
+            };
+            assert!(syscall.time.entry <= syscall.time.exit);
+            assert!(syscall.time.realtime_entry.is_some());
+            if let Some(last_entry) = last_entry {
+                assert!(last_entry <= syscall.time.entry);
+            }
+            last_entry = Some(syscall.time.entry);
+        }
+        assert!(last_entry.is_some());
+    }
+
+    #[test]
+    fn trace_filter() {
+        initialize();
+        let opts = SyscallIterOpts::default()
+            .skip_to_main(false)
+            .filter("write,close".parse().unwrap());
+        let child = Tracer::command(Command::new("./read_limit.exec"))
+            .current_dir("test_programs/build/")
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let syscalls: Vec<_> = child
+            .filter_map(|call| match call.unwrap() {
+                TraceData::Syscall(syscall) => Some(syscall.syscall),
+                TraceData::Event(_) => None,
+            })
+            .collect();
+        assert!(!syscalls.is_empty());
+        assert!(
+            syscalls
+                .iter()
+                .all(|call| matches!(call, SyscallInfo::Write { .. } | SyscallInfo::Close { .. }))
+        );
+    }
+
+    #[test]
+    fn seccomp_filter() {
+        initialize();
+        let opts = SyscallIterOpts::default()
+            .filter("write".parse().unwrap())
+            .seccomp(true);
+        let mut child = Tracer::command(Command::new("./threads.exec"))
+            .current_dir("test_programs/build/")
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let trace = child.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        // threads are not traced, but their writes must not fail
+        let (writes, events): (Vec<_>, Vec<_>) = trace
+            .into_iter()
+            .partition(|call| matches!(call, TraceData::Syscall(_)));
+        assert_eq!(writes.len(), 2);
+        assert_eq!(events, vec![TraceData::Event(TraceEvent::ProcessExit(0))]);
+        assert!(child.exit_status().unwrap().success());
+    }
+
+    #[test]
+    fn seccomp_rejects_exec() {
+        let filter: SyscallFilter = "openat,execveat".parse().unwrap();
+        assert_eq!(
+            SeccompProgram::new(&filter).unwrap_err(),
+            UntraceableSyscall("execveat")
+        );
+        for filter in ["execve", "!write"] {
+            let opts = SyscallIterOpts::default()
+                .filter(filter.parse().unwrap())
+                .seccomp(true);
+            let err = Tracer::command(Command::new("./threads.exec"))
+                .current_dir("test_programs/build/")
+                .options(opts)
+                .spawn()
+                .err()
+                .unwrap();
+            assert!(
+                matches!(&err, SpawnError::Spawn(err) if err.kind() == io::ErrorKind::InvalidInput),
+                "{err}"
+            );
+        }
+    }
+
+    fn trace_read_limit(limits: CaptureLimits) -> Vec<SyscallInfo> {
+        let child = Tracer::command(Command::new("./read_limit.exec"))
+            .current_dir("test_programs/build/")
+            .options(SyscallIterOpts::default().capture_limits(limits))
+            .spawn()
+            .unwrap();
+        child
+            .filter_map(|call| match call.unwrap() {
+                TraceData::Syscall(syscall) => Some(syscall.syscall),
+                TraceData::Event(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn capture_limits() {
+        initialize();
+        let limits = CaptureLimits {
+            max_buffer: 16,
+            max_string: 5,
+            ..CaptureLimits::default()
+        };
+        let syscalls = trace_read_limit(limits);
+
+        let Some(SyscallInfo::Openat { pathname, .. }) = syscalls.first() else {
+            panic!("expected openat, got {syscalls:?}");
+        };
+        assert_eq!(pathname.data, b"/tmp/");
+        assert_eq!(pathname.original_len, "/tmp/test_file\0".len());
+        assert!(pathname.truncated);
+
+        let Some(SyscallInfo::Write { to_write, .. }) = syscalls.get(1) else {
+            panic!("expected write, got {syscalls:?}");
+        };
+        assert_eq!(to_write.data, [5; 16]);
+        assert_eq!(to_write.original_len, 256);
+        assert!(to_write.truncated);
+    }
+
+    #[test]
+    fn capture_no_data() {
+        initialize();
+        let syscalls = trace_read_limit(CaptureLimits::NO_DATA);
+        let Some(SyscallInfo::Write { to_write, .. }) = syscalls.get(1) else {
+            panic!("expected write, got {syscalls:?}");
+        };
+        assert!(to_write.data.is_empty());
+        assert_eq!(to_write.original_len, 256);
+
+        let Some(SyscallInfo::Unlink { pathname }) = syscalls.get(3) else {
+            panic!("expected unlink, got {syscalls:?}");
+        };
+        assert!(pathname.data.is_empty());
+        assert!(pathname.truncated);
+        assert_eq!(pathname.without_nul(), b"");
+    }
+
+    fn trace_exec(args: &[&str], filter: &str) -> Vec<SyscallInfo> {
+        let mut cmd = Command::new("./exec_args.exec");
+        cmd.args(args);
+        let child = Tracer::command(cmd)
+            .current_dir("test_programs/build/")
+            .options(SyscallIterOpts::default().filter(filter.parse().unwrap()))
+            .spawn()
+            .unwrap();
+        child
+            .filter_map(|call| match call.unwrap() {
+                TraceData::Syscall(syscall) => Some(syscall.syscall),
+                TraceData::Event(_) => None,
+            })
+            .collect()
+    }
+
+    fn strings(strings: &[CapturedBytes]) -> Vec<&[u8]> {
+        strings.iter().map(CapturedBytes::without_nul).collect()
+    }
+
+    #[test]
+    fn execve_args() {
+        initialize();
+        let syscalls = trace_exec(&[], "execve");
+        let [
+            SyscallInfo::Execve {
+                pathname,
+                argv,
+                envp,
+            },
+        ] = syscalls.as_slice()
+        else {
+            panic!("expected single execve, got {syscalls:?}");
+        };
+        assert_eq!(pathname.without_nul(), b"/bin/true");
+        assert_eq!(strings(argv), [b"true".as_slice(), b"--flag", b"two words"]);
+        assert_eq!(strings(envp), [b"FOO=bar".as_slice(), b"EMPTY="]);
+    }
+
+    #[test]
+    fn execveat_args() {
+        initialize();
+        let syscalls = trace_exec(&["at"], "execveat");
+        let [
+            SyscallInfo::Execveat {
+                dirfd,
+                pathname,
+                argv,
+                envp,
+                flags,
+            },
+        ] = syscalls.as_slice()
+        else {
+            panic!("expected single execveat, got {syscalls:?}");
+        };
+        assert_eq!(*dirfd, libc::AT_FDCWD);
+        assert_eq!(flags.bits(), 0);
+        assert_eq!(pathname.without_nul(), b"/bin/true");
+        assert_eq!(strings(argv), [b"true".as_slice(), b"--flag", b"two words"]);
+        assert_eq!(envp.len(), 2);
+    }
+
+    /// Traces `exec.exec` until the first syscall after its successful exec, the new program runs
+    /// for a long time so it's killed afterwards
+    fn trace_exec_event(opts: SyscallIterOpts) -> Vec<TraceData> {
+        let mut child = Tracer::command(Command::new("./exec.exec"))
+            .current_dir("test_programs/build/")
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let mut trace = vec![];
+        for call in child.by_ref() {
+            let call = call.unwrap();
+            let exec = matches!(call, TraceData::Event(TraceEvent::Exec { .. }));
+            trace.push(call);
+            if exec {
+                break;
+            }
+        }
+        trace.extend(child.next().map(Result::unwrap));
+        child.kill().unwrap();
+        let _ = child.wait();
+        trace
+    }
+
+    fn assert_exec_event(trace: &[TraceData]) {
+        let position = trace
+            .iter()
+            .position(|call| matches!(call, TraceData::Event(TraceEvent::Exec { .. })))
+            .unwrap_or_else(|| panic!("no exec event in {trace:?}"));
+        let TraceData::Event(TraceEvent::Exec { executable }) = &trace[position] else {
+            unreachable!();
+        };
+        assert!(executable.ends_with(b"/test_programs/build/open.exec"));
+    }
+
+    #[test]
+    fn exec_event() {
+        initialize();
+        let opts = SyscallIterOpts::default().filter("execve,openat".parse().unwrap());
+        let trace = trace_exec_event(opts);
+
+        let [
+            TraceData::Event(TraceEvent::SyscallError {
+                syscall: SyscallInfoDiscriminants::Execve,
+                error: Errno::ENOENT,
+                ..
+            }),
+            TraceData::Syscall(Syscall {
+                syscall: SyscallInfo::Execve { pathname, .. },
+                ..
+            }),
+            TraceData::Event(TraceEvent::Exec { .. }),
+            ..,
+        ] = trace.as_slice()
+        else {
+            panic!("unexpected trace {trace:?}");
+        };
+        assert_eq!(pathname.without_nul(), b"./open.exec");
+        assert_exec_event(&trace);
+    }
+
+    #[test]
+    fn exec_skip_to_main() {
+        initialize();
+        let opts = SyscallIterOpts::default().skip_to_main_after_exec(true);
+        let trace = trace_exec_event(opts);
+        assert_exec_event(&trace);
+
+        // the dynamic loader of the new program is skipped
+        let Some(TraceData::Syscall(Syscall {
+            syscall: SyscallInfo::Openat { pathname, .. },
+            ..
+        })) = trace.last()
+        else {
+            panic!("expected openat after exec, got {trace:?}");
+        };
+        assert_eq!(pathname.without_nul(), b"/tmp/test.txt");
+    }
+
+    #[test]
+    fn exec_event_seccomp() {
+        initialize();
+        let filter: SyscallFilter = "openat".parse().unwrap();
+        let opts = SyscallIterOpts::default()
+            .filter(filter)
+            .seccomp(true)
+            .skip_to_main_after_exec(true);
+        let trace = trace_exec_event(opts);
+        assert_exec_event(&trace);
+        assert!(matches!(
+            trace.last(),
+            Some(TraceData::Syscall(Syscall {
+                syscall: SyscallInfo::Openat { .. },
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn stack_frames() {
+        initialize();
+        let opts = SyscallIterOpts::default()
+            .filter("write".parse().unwrap())
+            .stack_depth(2);
+        let child = Tracer::command(Command::new("./stack.exec"))
+            .current_dir("test_programs/build/")
+            .stdout(Stdio::null())
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let trace = child.collect::<Result<Vec<_>, _>>().unwrap();
+        let Some(TraceData::Syscall(write)) = trace.first() else {
+            panic!("expected write, got {trace:?}");
+        };
+        // write() returns to inner() which returns to outer()
+        assert_eq!(write.stack.len(), 2);
+        for frame in &write.stack {
+            let module = frame.module.as_deref().unwrap();
+            assert!(module.ends_with("/stack.exec"), "{module}");
+        }
+        let mut symbolizer = Symbolizer::new();
+        let functions: Vec<_> = write
+            .stack
+            .iter()
+            .map(|call_site| symbolizer.symbolize(call_site).unwrap().function)
+            .collect();
+        assert_eq!(functions, ["inner", "outer"]);
+    }
+
+    fn first_syscall(exec: &str) -> SyscallInfo {
+        let mut child = Tracer::command(Command::new(exec))
+            .current_dir("test_programs/build/")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let first = child.next().unwrap().unwrap();
+        // the tracee is left stopped in the write
+        child.kill().unwrap();
+        let _ = child.wait();
+        let TraceData::Syscall(syscall) = first else {
+            panic!("expected a syscall, got {first:?}");
+        };
+        syscall.syscall
+    }
+
+    #[test]
+    fn skip_to_main() {
+        initialize();
+        // the write in main is the first syscall, nothing from the dynamic loader or libc init
+        for exec in [
+            "./skip_main.exec",
+            "./skip_main_stripped.exec",
+            "./skip_main_static.exec",
+        ] {
+            let syscall = first_syscall(exec);
+            assert!(
+                matches!(&syscall, SyscallInfo::Write { fd: 1, .. }),
+                "{exec}: {syscall:?}"
+            );
+        }
+    }
 }
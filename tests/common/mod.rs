@@ -0,0 +1,81 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code, reason = "each test uses only some of the helpers")]
+use std::{
+    process::{Command, ExitStatus, Stdio},
+    time::Duration,
+};
+
+use boubo_trace::{
+    syscall::{
+        CallSite, Syscall, SyscallInfo, SyscallIterOpts, SyscallTime, TraceData,
+        capture::CapturedBytes, handler::TraceHandler,
+    },
+    tracer::Tracer,
+};
+
+/// Builds the programs in `test_programs/build/`
+pub fn make() {
+    Command::new("make")
+        .current_dir("test_programs/")
+        .status()
+        .unwrap();
+}
+
+/// Builds the test programs and traces `command` run in `test_programs/build/`, its output is
+/// discarded
+pub fn tracer(command: Command) -> Tracer {
+    make();
+    Tracer::command(command)
+        .current_dir("test_programs/build/")
+        .stdout(Stdio::null())
+}
+
+/// Trace of `command` run by [`tracer`] with `opts`
+pub fn trace(command: Command, opts: SyscallIterOpts) -> Vec<TraceData> {
+    let child = tracer(command).options(opts).spawn().unwrap();
+    child.collect::<Result<Vec<_>, _>>().unwrap()
+}
+
+/// Trace and exit status of `command` run by [`tracer`] with `opts`, calling hooks of `handler`
+pub fn trace_with(
+    command: Command,
+    opts: SyscallIterOpts,
+    handler: &mut dyn TraceHandler,
+) -> (Vec<TraceData>, ExitStatus) {
+    let mut child = tracer(command).options(opts).spawn().unwrap();
+    let mut trace = vec![];
+    while let Some(data) = child.next_with(handler) {
+        trace.push(data.unwrap());
+    }
+    (trace, child.wait().unwrap())
+}
+
+/// All of `data` captured
+pub fn bytes(data: &[u8]) -> CapturedBytes {
+    CapturedBytes::new(data.to_vec(), usize::MAX)
+}
+
+/// Time of a syscall entered `entry` µs after boot which took `duration` µs
+pub fn time(entry: u64, duration: u64) -> SyscallTime {
+    SyscallTime {
+        entry: Duration::from_micros(entry),
+        exit: Duration::from_micros(entry + duration),
+        realtime_entry: None,
+    }
+}
+
+/// Record of `syscall` made at `time` from an unknown call site
+pub fn record(syscall: SyscallInfo, time: SyscallTime) -> Syscall {
+    Syscall {
+        syscall,
+        cpu_time: 0.0,
+        time,
+        call_site: CallSite::default(),
+        stack: vec![],
+    }
+}
+
+/// [`record`] as traced data
+pub fn syscall(syscall: SyscallInfo, time: SyscallTime) -> TraceData {
+    TraceData::Syscall(record(syscall, time))
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use boubo_trace::syscall::flags::{CloneFlags, FileMode, OpenFlags, RandomFlags, SocketType};
+    use nix::sys::signal::Signal;
+
+    #[test]
+    fn known_and_unknown_bits() {
+        let flags = OpenFlags(libc::O_WRONLY | libc::O_CREAT | 0x4000_0000);
+        assert_eq!(flags.unknown(), 0x4000_0000);
+        assert_eq!(flags.to_string(), "O_CREAT | O_WRONLY | unknown 0x40000000");
+        assert_eq!(
+            format!("{flags:?}"),
+            "OpenFlags(O_CREAT | O_WRONLY | unknown 0x40000000)"
+        );
+        assert_eq!(OpenFlags(libc::O_RDONLY).to_string(), "0");
+        assert_eq!(FileMode(0o644).unknown(), 0);
+        assert_eq!(RandomFlags(0x10).to_string(), "unknown 0x10");
+    }
+
+    #[test]
+    fn clone_exit_signal() {
+        let flags = CloneFlags((libc::CLONE_VM | libc::SIGCHLD) as u64 | 1 << 40);
+        assert_eq!(flags.exit_signal(), Some(Signal::SIGCHLD));
+        assert_eq!(
+            flags.to_string(),
+            "CLONE_VM | SIGCHLD | unknown 0x10000000000"
+        );
+    }
+
+    #[test]
+    fn socket_type() {
+        let r#type = SocketType(libc::SOCK_DGRAM | libc::SOCK_CLOEXEC);
+        assert_eq!(r#type.type_name(), Some("SOCK_DGRAM"));
+        assert_eq!(r#type.to_string(), "SOCK_DGRAM | SOCK_CLOEXEC");
+        assert_eq!(SocketType(0xe).to_string(), "unknown 0xe");
+    }
+}
@@ -0,0 +1,65 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::syscall::{
+        SyscallInfo, SyscallInfoDiscriminants, SyscallNewTypeError, TraceData,
+        parse_error::TraceEvent,
+    };
+    use nix::errno::Errno;
+
+    use crate::common::tracer;
+
+    /// Traces `fuzz.exec` calling decoded syscalls with random arguments generated from `seed`
+    fn trace_fuzz(seed: u64) -> Vec<TraceData> {
+        let mut cmd = Command::new("./fuzz.exec");
+        cmd.arg(seed.to_string());
+        let child = tracer(cmd).spawn().unwrap();
+        child.collect::<Result<_, _>>().unwrap()
+    }
+
+    /// Number of calls of `syscall` which failed with `EFAULT`
+    fn efaults(trace: &[TraceData], syscall: SyscallInfoDiscriminants) -> usize {
+        trace
+            .iter()
+            .filter(|data| {
+                matches!(
+                    data,
+                    TraceData::Event(TraceEvent::SyscallError { syscall: failed, error: Errno::EFAULT, .. })
+                        if *failed == syscall
+                )
+            })
+            .count()
+    }
+
+    #[test]
+    fn random_args() {
+        for seed in 1..=4 {
+            let trace = trace_fuzz(seed);
+            assert_eq!(
+                trace.last(),
+                Some(&TraceData::Event(TraceEvent::ProcessExit(0))),
+                "seed {seed}"
+            );
+            // unknown open flags are kept
+            assert!(trace.iter().any(|data| matches!(
+                data,
+                TraceData::Syscall(syscall) if matches!(
+                    syscall.syscall,
+                    SyscallInfo::Openat { flags, .. } if flags.unknown() != 0
+                )
+            )));
+
+            // they only take bad pointers, every call fails before doing anything
+            assert_eq!(efaults(&trace, SyscallInfoDiscriminants::Unlink), 100);
+            assert_eq!(efaults(&trace, SyscallInfoDiscriminants::Execve), 100);
+
+            let bytes = rkyv::to_bytes::<SyscallNewTypeError>(&trace).unwrap();
+            let deserialized =
+                rkyv::from_bytes::<Vec<TraceData>, SyscallNewTypeError>(&bytes).unwrap();
+            assert_eq!(deserialized, trace, "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::{
+        syscall::{
+            Syscall, SyscallInfo, SyscallIterOpts, TraceData,
+            handler::{Action, SyscallEntry, TraceHandler},
+            parse_error::TraceEvent,
+            table,
+        },
+        tracee::Tracee,
+    };
+    use nix::libc;
+
+    use crate::common::trace_with;
+
+    /// Denies all `openat` calls
+    struct Deny {
+        exits: Vec<TraceEvent>,
+    }
+
+    impl TraceHandler for Deny {
+        fn on_syscall_enter(&mut self, _: &mut Tracee, entry: &SyscallEntry) -> Action {
+            if table::syscall_name(entry.number) == Some("openat") {
+                Action::Skip(-i64::from(libc::ENOENT))
+            } else {
+                Action::Continue
+            }
+        }
+
+        fn on_exit(&mut self, _: &mut Tracee, event: &TraceEvent) {
+            self.exits.push(event.clone());
+        }
+    }
+
+    #[test]
+    fn skip_syscall() {
+        let mut handler = Deny { exits: vec![] };
+        let (trace, _) = trace_with(
+            Command::new("./inject.exec"),
+            SyscallIterOpts::default(),
+            &mut handler,
+        );
+        let skipped = trace
+            .iter()
+            .filter(|data| {
+                matches!(
+                    data,
+                    TraceData::Event(TraceEvent::SyscallSkipped { return_value, .. })
+                        if *return_value == -i64::from(libc::ENOENT)
+                )
+            })
+            .count();
+        // the dynamic loader opens libraries too
+        assert!(skipped >= 3);
+        assert!(matches!(
+            trace.last(),
+            Some(TraceData::Event(TraceEvent::ProcessExit(30)))
+        ));
+        assert!(matches!(
+            handler.exits.as_slice(),
+            [TraceEvent::ProcessExit(30)]
+        ));
+    }
+
+    /// Makes `getpid` return 42
+    struct FakePid;
+
+    impl TraceHandler for FakePid {
+        fn on_syscall_exit(
+            &mut self,
+            _: &mut Tracee,
+            syscall: Result<&Syscall, &TraceEvent>,
+        ) -> Action {
+            match syscall {
+                Ok(Syscall {
+                    syscall: SyscallInfo::Unknown { id: 39, .. },
+                    ..
+                }) => Action::SetReturnValue(42),
+                _ => Action::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn set_return_value() {
+        let (trace, status) = trace_with(
+            Command::new("./tamper.exec"),
+            SyscallIterOpts::default().filter("getpid,exit_group".parse().unwrap()),
+            &mut FakePid,
+        );
+        let original = trace
+            .iter()
+            .find_map(|data| match data {
+                TraceData::Event(TraceEvent::Tampered {
+                    id: 39,
+                    original,
+                    tampered: 42,
+                    ..
+                }) => Some(*original),
+                _ => None,
+            })
+            .unwrap();
+        // like with tamper rules, the record keeps the real pid
+        assert!(trace.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(Syscall {
+                syscall: SyscallInfo::Unknown { id: 39, return_value, .. },
+                ..
+            }) if *return_value == original
+        )));
+        assert!(matches!(
+            trace.last(),
+            Some(TraceData::Event(TraceEvent::ProcessExit(1)))
+        ));
+        assert_eq!(status.code(), Some(1));
+    }
+
+    /// Detaches at the first `openat` of the program
+    struct DetachOnOpen;
+
+    impl TraceHandler for DetachOnOpen {
+        fn on_syscall_enter(&mut self, _: &mut Tracee, entry: &SyscallEntry) -> Action {
+            if table::syscall_name(entry.number) == Some("openat") {
+                Action::Detach
+            } else {
+                Action::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn detach() {
+        let (trace, status) = trace_with(
+            Command::new("./inject.exec"),
+            SyscallIterOpts::default(),
+            &mut DetachOnOpen,
+        );
+        assert!(matches!(
+            trace.last(),
+            Some(TraceData::Event(TraceEvent::Detached))
+        ));
+        assert!(!trace.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(Syscall {
+                syscall: SyscallInfo::Openat { .. },
+                ..
+            })
+        )));
+        // the untraced program opens its file normally
+        assert_eq!(status.code(), Some(0));
+    }
+}
@@ -0,0 +1,314 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        process::Command,
+        time::{Duration, Instant},
+    };
+
+    use boubo_trace::syscall::{
+        SyscallInfo, SyscallIterOpts, TraceData,
+        inject::{Delay, DelayPoint, Fault, RuleParseError, Tamper, TamperAction},
+        parse_error::TraceEvent,
+        table,
+    };
+    use nix::errno::Errno;
+
+    use crate::common::trace;
+
+    fn trace_faults(rules: &[&str]) -> Vec<TraceData> {
+        trace(
+            Command::new("./inject.exec"),
+            rules.iter().fold(
+                SyscallIterOpts::default().filter("openat,write".parse().unwrap()),
+                |opts, rule| opts.inject_fault(rule.parse().unwrap()),
+            ),
+        )
+    }
+
+    fn injected(data: &TraceData) -> Option<(&str, Errno)> {
+        match data {
+            TraceData::Event(TraceEvent::FaultInjected { id, error, .. }) => {
+                Some((table::syscall_name(*id).unwrap(), *error))
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parse_rules() {
+        let fault = "openat:error=ENOENT:path=/etc/config:when=3"
+            .parse::<Fault>()
+            .unwrap();
+        assert_eq!(fault.error, Errno::ENOENT);
+        assert_eq!(
+            "write:error=11".parse::<Fault>().unwrap().error,
+            Errno::EAGAIN
+        );
+        assert_eq!(
+            "write:notanoption:error=EIO".parse::<Fault>().unwrap_err(),
+            RuleParseError::InvalidOption("notanoption".to_owned())
+        );
+        assert_eq!(
+            "write:when=1".parse::<Fault>().unwrap_err(),
+            RuleParseError::MissingOption("error")
+        );
+        assert_eq!(
+            "write:error=EWHAT".parse::<Fault>().unwrap_err(),
+            RuleParseError::UnknownErrno("EWHAT".to_owned())
+        );
+        assert!(matches!(
+            "write:error=EIO:probability=2".parse::<Fault>(),
+            Err(RuleParseError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            "nosuchcall:error=EIO".parse::<Fault>(),
+            Err(RuleParseError::Filter(_))
+        ));
+    }
+
+    #[test]
+    fn nth_openat() {
+        let trace = trace_faults(&["openat:error=ENOENT:path=./inject.exec:when=3"]);
+        let opens = trace
+            .iter()
+            .filter(|data| match data {
+                TraceData::Syscall(syscall) => {
+                    matches!(syscall.syscall, SyscallInfo::Openat { .. })
+                }
+                data @ TraceData::Event(_) => injected(data).is_some(),
+            })
+            .collect::<Vec<_>>();
+        // the first two are real, the third one failed without being run
+        assert_eq!(opens.len(), 3);
+        assert!(matches!(opens[0], TraceData::Syscall(_)));
+        assert!(matches!(opens[1], TraceData::Syscall(_)));
+        assert_eq!(injected(opens[2]), Some(("openat", Errno::ENOENT)));
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(10)))
+        );
+    }
+
+    #[test]
+    fn socket_writes() {
+        let trace = trace_faults(&["write:error=EAGAIN:fd=socket:*"]);
+        let faults = trace.iter().filter_map(injected).collect::<Vec<_>>();
+        assert_eq!(faults, [("write", Errno::EAGAIN)]);
+        // the write to stdout isn't a socket
+        assert!(trace.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(syscall) if matches!(syscall.syscall, SyscallInfo::Write { fd: 1, .. })
+        )));
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(1)))
+        );
+    }
+
+    #[test]
+    fn probability() {
+        let trace = trace_faults(&["openat:error=ENOENT:path=./inject.exec:probability=0"]);
+        assert!(trace.iter().all(|data| injected(data).is_none()));
+        let trace = trace_faults(&["openat:error=ENOENT:path=./inject.exec:probability=1"]);
+        assert_eq!(trace.iter().filter_map(injected).count(), 3);
+    }
+
+    #[test]
+    fn parse_delays() {
+        let delay = "recvfrom:delay=200ms:fd=3".parse::<Delay>().unwrap();
+        assert_eq!(delay.duration, Duration::from_millis(200));
+        assert_eq!(delay.at, DelayPoint::Entry);
+        let delay = "read:fd=socket:*:delay=2s:at=exit"
+            .parse::<Delay>()
+            .unwrap();
+        assert_eq!(delay.duration, Duration::from_secs(2));
+        assert_eq!(delay.at, DelayPoint::Exit);
+        assert_eq!(
+            "read:at=exit".parse::<Delay>().unwrap_err(),
+            RuleParseError::MissingOption("delay")
+        );
+        assert_eq!(
+            "read:delay=200".parse::<Delay>().unwrap_err(),
+            RuleParseError::InvalidOption("delay=200".to_owned())
+        );
+        assert_eq!(
+            "read:delay=1s:at=return".parse::<Delay>().unwrap_err(),
+            RuleParseError::InvalidOption("at=return".to_owned())
+        );
+    }
+
+    #[test]
+    fn delays() {
+        let delay = Duration::from_millis(200);
+        let opts = SyscallIterOpts::default()
+            .filter("write".parse().unwrap())
+            .inject_delay("write:delay=200ms:fd=socket:*".parse().unwrap())
+            .inject_delay("write:delay=200ms:fd=1:at=exit".parse().unwrap());
+        let start = Instant::now();
+        let trace = trace(Command::new("./inject.exec"), opts);
+        // the tracee was held, but not in the measured time of the syscalls
+        assert!(start.elapsed() >= 2 * delay);
+        let writes = trace
+            .iter()
+            .skip_while(|data| !matches!(data, TraceData::Event(_)))
+            .collect::<Vec<_>>();
+        // each delay is reported right before its syscall
+        let [
+            TraceData::Event(TraceEvent::DelayInjected {
+                id: 1,
+                delay: socket_delay,
+                at: DelayPoint::Entry,
+            }),
+            TraceData::Syscall(socket_write),
+            TraceData::Event(TraceEvent::DelayInjected {
+                id: 1,
+                delay: stdout_delay,
+                at: DelayPoint::Exit,
+            }),
+            TraceData::Syscall(stdout_write),
+            TraceData::Event(TraceEvent::ProcessExit(0)),
+        ] = writes.as_slice()
+        else {
+            panic!("unexpected trace {writes:?}");
+        };
+        assert_eq!((*socket_delay, *stdout_delay), (delay, delay));
+        assert!(matches!(
+            socket_write.syscall,
+            SyscallInfo::Write { fd: 3, .. }
+        ));
+        assert!(matches!(
+            stdout_write.syscall,
+            SyscallInfo::Write { fd: 1, .. }
+        ));
+        assert!(socket_write.time.duration() < delay);
+        assert!(stdout_write.time.duration() < delay);
+    }
+
+    fn trace_tampers(rules: &[&str]) -> Vec<TraceData> {
+        trace(
+            Command::new("./tamper.exec"),
+            rules.iter().fold(
+                SyscallIterOpts::default().filter("getpid,read".parse().unwrap()),
+                |opts, rule| opts.tamper(rule.parse().unwrap()),
+            ),
+        )
+    }
+
+    fn tampered(data: &TraceData) -> Option<(&str, i64, i64, usize)> {
+        match data {
+            TraceData::Event(TraceEvent::Tampered {
+                id,
+                original,
+                tampered,
+                written,
+            }) => Some((
+                table::syscall_name(*id).unwrap(),
+                *original,
+                *tampered,
+                *written,
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parse_tampers() {
+        let tamper = "getpid:return=-1".parse::<Tamper>().unwrap();
+        assert_eq!(tamper.action, TamperAction::Return(-1));
+        let tamper = "read:fd=3:content=a:b".parse::<Tamper>().unwrap();
+        assert_eq!(tamper.action, TamperAction::Content(b"a:b".to_vec()));
+        assert_eq!(
+            "read:fd=3".parse::<Tamper>().unwrap_err(),
+            RuleParseError::MissingAction
+        );
+        assert_eq!(
+            "read:truncate=1:return=1".parse::<Tamper>().unwrap_err(),
+            RuleParseError::InvalidOption("return=1".to_owned())
+        );
+        assert_eq!(
+            "read:content=@/nonexistent".parse::<Tamper>().unwrap_err(),
+            RuleParseError::Fixture("/nonexistent".to_owned())
+        );
+    }
+
+    #[test]
+    fn format_rules() {
+        for rule in [
+            "openat:path=/etc/*:error=ENOENT",
+            "write:fd=3:when=2:error=EIO",
+            "read:probability=0.5:seed=7:error=EAGAIN",
+        ] {
+            assert_eq!(rule.parse::<Fault>().unwrap().to_string(), rule);
+        }
+        for rule in [
+            "recvfrom:fd=socket:*:delay=200ms",
+            "write:delay=1500us:at=exit",
+            "read:delay=2s",
+        ] {
+            assert_eq!(rule.parse::<Delay>().unwrap().to_string(), rule);
+        }
+        for rule in [
+            "getpid:return=-1",
+            "read:fd=0:truncate=3",
+            "read:content=a:b",
+        ] {
+            assert_eq!(rule.parse::<Tamper>().unwrap().to_string(), rule);
+        }
+    }
+
+    #[test]
+    fn fake_return_value() {
+        let trace = trace_tampers(&["getpid:return=42"]);
+        let getpid = trace
+            .iter()
+            .position(|data| tampered(data).is_some_and(|(name, ..)| name == "getpid"))
+            .unwrap();
+        // the event comes right before the record, which keeps the real pid
+        let Some((_, original, 42, 0)) = tampered(&trace[getpid]) else {
+            panic!("expected getpid tampered to 42, got {:?}", trace[getpid]);
+        };
+        assert!(matches!(
+            &trace[getpid + 1],
+            TraceData::Syscall(syscall) if matches!(
+                syscall.syscall,
+                SyscallInfo::Unknown { return_value, .. } if return_value == original
+            )
+        ));
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(1)))
+        );
+    }
+
+    #[test]
+    fn truncated_read() {
+        let trace = trace_tampers(&["read:truncate=2:fd=*/tamper.exec"]);
+        let tampers = trace.iter().filter_map(tampered).collect::<Vec<_>>();
+        assert_eq!(tampers, [("read", 16, 2, 0); 2]);
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(10)))
+        );
+    }
+
+    #[test]
+    fn fixture_content() {
+        let trace = trace_tampers(&["read:content=fixed:fd=*/tamper.exec"]);
+        let tampers = trace.iter().filter_map(tampered).collect::<Vec<_>>();
+        assert_eq!(tampers, [("read", 16, 5, 5), ("read", 16, 0, 0)]);
+        // the record keeps what the kernel read
+        assert!(trace.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(syscall) if matches!(
+                &syscall.syscall,
+                SyscallInfo::Read { read_bytes, .. } if read_bytes.data.starts_with(b"\x7fELF")
+            )
+        )));
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(60)))
+        );
+    }
+}
@@ -0,0 +1,90 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::{
+        syscall::{SyscallIter, SyscallIterOpts, TraceData},
+        tracee::{
+            Tracee,
+            maps::{MemoryMapping, parse_maps},
+        },
+    };
+    use nix::{errno::Errno, unistd::Pid};
+    use spawn_ptrace::CommandPtraceSpawn;
+
+    use crate::common::make;
+
+    const MAPS: &str = "\
+55d0c8a00000-55d0c8a01000 r--p 00000000 00:1f 1234                       /tmp/a b.exec
+55d0c8a01000-55d0c8a02000 r-xp 00001000 00:1f 1234                       /tmp/a b.exec
+7ffd4e5f0000-7ffd4e611000 rw-p 00000000 00:00 0                          [stack]
+7f0c1c400000-7f0c1c402000 rw-p 00000000 00:00 0
+not a mapping
+7ffd4e7fa000-7ffd4e7fc000 r-xp 00000000 00:00 0                          [vdso]
+";
+
+    #[test]
+    fn parses_maps() {
+        let maps = parse_maps(MAPS);
+        assert_eq!(maps.len(), 5);
+        assert_eq!(
+            maps[1],
+            MemoryMapping {
+                start: 0x55d0_c8a0_1000,
+                end: 0x55d0_c8a0_2000,
+                permissions: "r-xp".to_owned(),
+                offset: 0x1000,
+                pathname: Some("/tmp/a b.exec".to_owned()),
+            }
+        );
+        assert!(maps[1].is_file());
+        assert_eq!(maps[1].offset_of(0x55d0_c8a0_1234), 0x1234);
+
+        assert_eq!(maps[3].pathname, None);
+        assert!(!maps[3].is_file());
+        assert_eq!(maps[4].pathname.as_deref(), Some("[vdso]"));
+        assert!(!maps[4].is_file());
+        assert_eq!(maps[4].offset_of(0x7ffd_4e7f_a010), 0x10);
+    }
+
+    #[test]
+    fn call_sites() {
+        make();
+        let mut cmd = Command::new("./read_limit.exec")
+            .current_dir("test_programs/build/")
+            .spawn_ptrace()
+            .unwrap();
+        let pid = Pid::from_raw(cmd.id().cast_signed());
+        let mut tracee = Tracee::new(pid);
+
+        let mappings = tracee.mappings().unwrap();
+        let vdso = mappings
+            .iter()
+            .find(|mapping| mapping.pathname.as_deref() == Some("[vdso]"));
+        if let Some(vdso) = vdso {
+            let call_site = tracee.resolve_call_site(&mappings, vdso.start + 8);
+            assert_eq!(call_site.module.as_deref(), Some("[vdso]"));
+            assert_eq!(call_site.build_id, None);
+            assert_eq!(call_site.offset, 8);
+        }
+        let outside = tracee.resolve_call_site(&mappings, 8);
+        assert_eq!(outside.module, None);
+        assert_eq!(outside.offset, 8);
+        // a null stack pointer or frame ends the stack walk
+        assert_eq!(tracee.memcpy(0, 8), Err(Errno::EFAULT));
+
+        let it = SyscallIter::new(tracee, &SyscallIterOpts::default()).unwrap();
+        for call in it {
+            let TraceData::Syscall(syscall) = call.unwrap() else {
+                continue;
+            };
+            // syscalls of the test program are made by libc
+            let module = syscall.call_site.module.unwrap();
+            assert!(module.contains("libc"), "{module}");
+            assert!(syscall.call_site.build_id.is_some_and(|id| !id.is_empty()));
+        }
+        let _ = cmd.wait();
+    }
+}
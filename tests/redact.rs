@@ -0,0 +1,161 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use boubo_trace::{
+        redact::{RedactMode, Redactor, glob_matches},
+        syscall::{
+            SyscallInfo,
+            capture::CapturedBytes,
+            flags::{FileMode, OpenFlags},
+        },
+        trace_file::TraceMetadata,
+    };
+
+    use crate::common::bytes;
+
+    fn write(fd: i32, data: &[u8]) -> SyscallInfo {
+        SyscallInfo::Write {
+            fd,
+            to_write: bytes(data),
+            written_count: data.len(),
+        }
+    }
+
+    fn written(syscall: &SyscallInfo) -> &CapturedBytes {
+        let SyscallInfo::Write { to_write, .. } = syscall else {
+            panic!("expected write, got {syscall:?}");
+        };
+        to_write
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches(b"*_TOKEN", b"GITHUB_TOKEN"));
+        assert!(glob_matches(b"AWS_*", b"AWS_SECRET_ACCESS_KEY"));
+        assert!(glob_matches(b"A?S*KEY", b"AWS_SECRET_ACCESS_KEY"));
+        assert!(glob_matches(b"*", b""));
+        assert!(!glob_matches(b"*_TOKEN", b"TOKEN"));
+        assert!(!glob_matches(b"AWS_*", b"MY_AWS_KEY"));
+    }
+
+    #[test]
+    fn env_and_regex() {
+        let mut redactor = Redactor::new(RedactMode::Remove)
+            .env_pattern("*_TOKEN")
+            .buffer_pattern("pass(word)?=[^ ]*")
+            .unwrap();
+
+        let mut execve = SyscallInfo::Execve {
+            pathname: bytes(b"/bin/sh\0"),
+            argv: vec![bytes(b"sh\0")],
+            envp: vec![bytes(b"HOME=/root\0"), bytes(b"API_TOKEN=secret\0")],
+        };
+        redactor.redact(&mut execve);
+        let SyscallInfo::Execve { pathname, envp, .. } = &execve else {
+            unreachable!();
+        };
+        assert!(!pathname.redacted);
+        assert!(!envp[0].redacted);
+        assert!(envp[1].redacted);
+        assert_eq!(envp[1].data, b"API_TOKEN=<redacted>\0");
+
+        let mut call = write(1, b"user=me password=hunter2 ok");
+        redactor.redact(&mut call);
+        assert_eq!(written(&call).data, b"user=me <redacted> ok");
+        assert_eq!(written(&call).original_len, 27);
+    }
+
+    #[test]
+    fn metadata() {
+        let redactor = Redactor::new(RedactMode::Remove)
+            .env_pattern("*_TOKEN")
+            .buffer_pattern("pass(word)?=[^ ]*")
+            .unwrap();
+        let mut metadata = TraceMetadata {
+            args: vec![b"--password=hunter2".to_vec()],
+            env: vec![
+                b"HOME=/root".to_vec(),
+                b"API_TOKEN=secret".to_vec(),
+                b"OPTS=pass=hunter2".to_vec(),
+            ],
+            tampers: vec!["read:content=password=hunter2".to_owned()],
+            ..TraceMetadata::default()
+        };
+        redactor.redact_metadata(&mut metadata);
+        assert_eq!(metadata.args, [b"--<redacted>".to_vec()]);
+        assert_eq!(
+            metadata.env,
+            [
+                b"HOME=/root".to_vec(),
+                b"API_TOKEN=<redacted>".to_vec(),
+                b"OPTS=<redacted>".to_vec(),
+            ]
+        );
+        assert_eq!(metadata.tampers, ["read:content=<redacted>"]);
+    }
+
+    #[test]
+    fn hashes_are_comparable() {
+        let mut redactor = Redactor::new(RedactMode::Hash).drop_fd(1);
+        let mut first = write(1, b"secret");
+        let mut second = write(1, b"secret");
+        let mut other = write(1, b"other");
+        let mut stderr = write(2, b"secret");
+        for call in [&mut first, &mut second, &mut other, &mut stderr] {
+            redactor.redact(call);
+        }
+        assert!(written(&first).data.starts_with(b"<hmac-sha256:"));
+        assert_eq!(written(&first), written(&second));
+        assert_ne!(written(&first).data, written(&other).data);
+        assert_eq!(written(&stderr).data, b"secret");
+    }
+
+    #[test]
+    fn hashes_are_keyed() {
+        let redact = |mut redactor: Redactor| {
+            let mut call = write(1, b"what do ya want for nothing?");
+            redactor = redactor.drop_fd(1);
+            redactor.redact(&mut call);
+            written(&call).data.clone()
+        };
+        // test case 2 of RFC 4231
+        assert_eq!(
+            redact(Redactor::new(RedactMode::Hash).hash_key("Jefe")),
+            b"<hmac-sha256:5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843>"
+        );
+        assert_ne!(
+            redact(Redactor::new(RedactMode::Hash)),
+            redact(Redactor::new(RedactMode::Hash))
+        );
+    }
+
+    #[test]
+    fn drop_path_follows_fd() {
+        let mut redactor = Redactor::new(RedactMode::Remove).drop_path("/etc/secrets");
+        let mut trace = [
+            SyscallInfo::Openat {
+                dirfd: libc::AT_FDCWD,
+                pathname: bytes(b"/etc/secrets/key\0"),
+                flags: OpenFlags(libc::O_RDONLY),
+                mode: FileMode(0),
+                opened_fd: 3,
+            },
+            write(3, b"key"),
+            SyscallInfo::Close { fd: 3 },
+            SyscallInfo::Openat {
+                dirfd: libc::AT_FDCWD,
+                pathname: bytes(b"/etc/secrets2\0"),
+                flags: OpenFlags(libc::O_RDONLY),
+                mode: FileMode(0),
+                opened_fd: 3,
+            },
+            write(3, b"not a key"),
+        ];
+        for call in &mut trace {
+            redactor.redact(call);
+        }
+        assert_eq!(written(&trace[1]).data, b"<redacted>");
+        assert_eq!(written(&trace[4]).data, b"not a key");
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::syscall::{
+        SyscallInfo, SyscallIterOpts, TraceData, parse_error::TraceEvent, replay::Replay,
+    };
+
+    use crate::common;
+
+    fn trace(args: &[&str], opts: SyscallIterOpts) -> Vec<TraceData> {
+        let mut cmd = Command::new("./replay.exec");
+        cmd.args(args);
+        common::trace(cmd, opts)
+    }
+
+    fn written(trace: &[TraceData]) -> Vec<&[u8]> {
+        trace
+            .iter()
+            .filter_map(|data| match data {
+                TraceData::Syscall(syscall) => match &syscall.syscall {
+                    SyscallInfo::Write { to_write, .. } => Some(to_write.data.as_slice()),
+                    _ => None,
+                },
+                TraceData::Event(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replay() {
+        // the content fills the whole buffer and is only in the recording
+        let mut recording = trace(&[], SyscallIterOpts::default());
+        for data in &mut recording {
+            if let TraceData::Syscall(syscall) = data
+                && let SyscallInfo::Read { read_bytes, .. } = &mut syscall.syscall
+            {
+                read_bytes.data = b"fixture!".to_vec();
+            }
+        }
+        let replayed = trace(
+            &[],
+            SyscallIterOpts::default().replay(Replay::new(&recording)),
+        );
+        assert_eq!(written(&replayed)[0], b"fixture!");
+        assert_eq!(written(&replayed)[1..], written(&recording)[1..]);
+        assert!(replayed.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(syscall) if syscall.syscall.is_clock_gettime()
+        )));
+        assert!(
+            !replayed
+                .iter()
+                .any(|data| matches!(data, TraceData::Event(TraceEvent::ReplayDiverged { .. })))
+        );
+        assert_eq!(
+            replayed.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(1)))
+        );
+    }
+
+    #[test]
+    fn divergence() {
+        let recording = trace(&[], SyscallIterOpts::default());
+        let replayed = trace(
+            &["diverge"],
+            SyscallIterOpts::default().replay(Replay::new(&recording)),
+        );
+        let diverged = replayed
+            .iter()
+            .find_map(|data| match data {
+                TraceData::Event(TraceEvent::ReplayDiverged {
+                    position,
+                    expected,
+                    actual,
+                }) => Some((*position, *expected, *actual)),
+                _ => None,
+            })
+            .unwrap();
+        let (position, expected, actual) = diverged;
+        assert_eq!(actual, libc::SYS_getppid as u64);
+        assert_eq!(expected, Some(libc::SYS_openat as u64));
+        assert!(matches!(
+            &recording[position],
+            TraceData::Syscall(syscall) if syscall.syscall.is_openat()
+        ));
+        // nothing is replayed after the divergence
+        assert_ne!(written(&replayed)[1], written(&recording)[1]);
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use std::{
+        fs,
+        io::{self, Write},
+        path::Path,
+        process::{Command, Stdio},
+        thread,
+        time::Duration,
+    };
+
+    use boubo_trace::{
+        syscall::{SyscallInfo, TraceData, parse_error::TraceEvent, stream::TraceStream},
+        tracer::{SpawnError, Tracer},
+    };
+    use futures::{StreamExt, executor};
+
+    use crate::common::tracer;
+
+    async fn stream(program: &str, capacity: usize) -> TraceStream {
+        TraceStream::spawn(capacity, tracer(Command::new(program)))
+            .await
+            .unwrap()
+    }
+
+    /// Waits until `pid` is no longer traced, the tracing thread reaps it once it exits
+    fn wait_detached(pid: u32) {
+        let status_file = format!("/proc/{pid}/status");
+        for _ in 0..500 {
+            match fs::read_to_string(&status_file) {
+                Ok(status) if status.lines().any(|line| line == "TracerPid:\t0") => return,
+                Err(_) => return,
+                Ok(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        panic!("{pid} is still traced");
+    }
+
+    /// Waits until the tracing thread reaped `pid`
+    fn wait_exited(pid: u32) {
+        for _ in 0..500 {
+            if !Path::new(&format!("/proc/{pid}")).exists() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("{pid} didn't exit");
+    }
+
+    #[test]
+    fn trace_stream() {
+        let trace = executor::block_on(async {
+            let stream = stream("./inject.exec", 1).await;
+            stream.collect::<Vec<_>>().await
+        });
+        let trace = trace.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(trace.iter().any(|data| matches!(
+            data,
+            TraceData::Syscall(syscall) if matches!(syscall.syscall, SyscallInfo::Openat { .. })
+        )));
+        assert!(matches!(
+            trace.last(),
+            Some(TraceData::Event(TraceEvent::ProcessExit(0)))
+        ));
+    }
+
+    #[test]
+    fn detach_on_drop() {
+        let pid = executor::block_on(async {
+            let mut stream = stream("./inject.exec", 1).await;
+            stream.next().await.unwrap().unwrap();
+            stream.id()
+        });
+        // the tracee was detached by the tracing thread and runs to its end untraced
+        wait_detached(pid);
+        wait_exited(pid);
+    }
+
+    #[test]
+    fn detach_blocked_tracee() {
+        let (input, mut writer) = io::pipe().unwrap();
+        let pid = executor::block_on(async {
+            let tracer = Tracer::command(Command::new("cat"))
+                .stdin(input)
+                .stdout(Stdio::null());
+            let stream = TraceStream::spawn(1000, tracer).await.unwrap();
+            // enough time for cat to block reading the empty pipe
+            thread::sleep(Duration::from_millis(300));
+            stream.id()
+        });
+        wait_detached(pid);
+        // the interrupted read is restarted and gets the input
+        writer.write_all(b"input\n").unwrap();
+        drop(writer);
+        wait_exited(pid);
+    }
+
+    #[test]
+    fn start_error() {
+        let tracer = Tracer::command(Command::new("./does_not_exist.exec"));
+        let result = executor::block_on(TraceStream::spawn(1, tracer));
+        assert!(matches!(result, Err(SpawnError::Spawn(_))));
+    }
+}
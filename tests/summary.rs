@@ -0,0 +1,149 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use boubo_trace::{
+        summary::{SummaryKey, SyscallStats, TraceSummary},
+        syscall::{
+            SyscallInfo, SyscallInfoDiscriminants, SyscallTime, TraceData, parse_error::TraceEvent,
+        },
+    };
+    use nix::errno::Errno;
+
+    use crate::common;
+
+    fn took(micros: u64) -> SyscallTime {
+        common::time(1_000_000, micros)
+    }
+
+    fn syscall(syscall: SyscallInfo, micros: u64) -> TraceData {
+        common::syscall(syscall, took(micros))
+    }
+
+    fn error(syscall: SyscallInfoDiscriminants, error: Errno, micros: u64) -> TraceData {
+        TraceData::Event(TraceEvent::SyscallError {
+            syscall,
+            error,
+            cpu_time: 0.0,
+            time: took(micros),
+            rip: 0,
+        })
+    }
+
+    #[test]
+    fn aggregates_calls_errors_and_time() {
+        let trace = [
+            syscall(SyscallInfo::Close { fd: 3 }, 10),
+            syscall(SyscallInfo::Close { fd: 4 }, 30),
+            error(SyscallInfoDiscriminants::Close, Errno::EBADF, 20),
+            error(SyscallInfoDiscriminants::Close, Errno::EBADF, 0),
+            error(SyscallInfoDiscriminants::Close, Errno::EINTR, 0),
+            syscall(
+                SyscallInfo::Unknown {
+                    id: 39,
+                    args: [0; 6],
+                    decoded_args: vec![],
+                    return_value: 100,
+                },
+                5,
+            ),
+            TraceData::Event(TraceEvent::ProcessExit(0)),
+        ];
+        let summary: TraceSummary = trace.iter().collect();
+
+        let close = summary
+            .get(SummaryKey::Known(SyscallInfoDiscriminants::Close))
+            .unwrap();
+        assert_eq!(close.calls, 5);
+        assert_eq!(close.errors, 3);
+        assert_eq!(close.total_time, Duration::from_micros(60));
+        assert_eq!(close.average_time(), Duration::from_micros(12));
+        assert_eq!(close.errnos, vec![(Errno::EBADF, 2), (Errno::EINTR, 1)]);
+
+        let unknown = summary.get(SummaryKey::Unknown(39)).unwrap();
+        assert_eq!(unknown.calls, 1);
+        assert_eq!(unknown.errors, 0);
+
+        let sorted: Vec<_> = summary.sorted().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                SummaryKey::Known(SyscallInfoDiscriminants::Close),
+                SummaryKey::Unknown(39)
+            ]
+        );
+
+        let total = summary.total();
+        assert_eq!(total.calls, 6);
+        assert_eq!(total.errors, 3);
+        assert_eq!(total.total_time, Duration::from_micros(65));
+    }
+
+    #[test]
+    fn average_of_many_calls() {
+        let stats = SyscallStats {
+            calls: 1 << 32,
+            total_time: Duration::from_secs(3 << 32),
+            ..SyscallStats::default()
+        };
+        assert_eq!(stats.average_time(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn zero_time() {
+        let summary: TraceSummary = [syscall(SyscallInfo::Close { fd: 3 }, 0)].iter().collect();
+        let output = summary.to_string();
+        let total = output.lines().last().unwrap();
+        assert!(total.starts_with("  0.00    0.000000"), "{output}");
+    }
+
+    #[test]
+    fn failed_unknown_injected_and_skipped() {
+        let unknown = |return_value| {
+            syscall(
+                SyscallInfo::Unknown {
+                    id: 83,
+                    args: [0; 6],
+                    decoded_args: vec![],
+                    return_value,
+                },
+                1,
+            )
+        };
+        let trace = [
+            unknown(0),
+            unknown(-i64::from(Errno::ENOENT as i32)),
+            unknown(-5000),
+            TraceData::Event(TraceEvent::FaultInjected {
+                id: libc::SYS_close as u64,
+                error: Errno::EIO,
+                time: took(2),
+            }),
+            TraceData::Event(TraceEvent::SyscallSkipped {
+                id: 83,
+                return_value: -i64::from(Errno::EPERM as i32),
+                time: took(3),
+            }),
+            TraceData::Event(TraceEvent::SyscallSkipped {
+                id: 83,
+                return_value: 0,
+                time: took(4),
+            }),
+        ];
+        let summary: TraceSummary = trace.iter().collect();
+
+        let mkdir = summary.get(SummaryKey::Unknown(83)).unwrap();
+        assert_eq!(mkdir.calls, 5);
+        assert_eq!(mkdir.errors, 2);
+        assert_eq!(mkdir.errnos, vec![(Errno::ENOENT, 1), (Errno::EPERM, 1)]);
+        assert_eq!(mkdir.total_time, Duration::from_micros(10));
+
+        let close = summary
+            .get(SummaryKey::Known(SyscallInfoDiscriminants::Close))
+            .unwrap();
+        assert_eq!(close.calls, 1);
+        assert_eq!(close.errnos, vec![(Errno::EIO, 1)]);
+    }
+}
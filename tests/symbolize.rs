@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::{
+        symbolize::Symbolizer,
+        syscall::{CallSite, SyscallIterOpts, TraceData},
+    };
+
+    use crate::common::trace;
+
+    #[test]
+    fn stack_functions() {
+        let opts = SyscallIterOpts::default()
+            .filter("write".parse().unwrap())
+            .stack_depth(3);
+        let trace = trace(Command::new("./stack.exec"), opts);
+        let Some(TraceData::Syscall(write)) = trace.first() else {
+            panic!("expected write, got {trace:?}");
+        };
+
+        let mut symbolizer = Symbolizer::new();
+        let frames = write
+            .stack
+            .iter()
+            .map(|call_site| symbolizer.symbolize(call_site).unwrap())
+            .collect::<Vec<_>>();
+        let functions: Vec<_> = frames.iter().map(|frame| frame.function.as_str()).collect();
+        assert_eq!(functions, ["inner", "outer", "main"]);
+        // the lines of the calls, not of the code the calls return to
+        #[cfg(feature = "dwarf")]
+        for (frame, line) in frames.iter().zip([6, 11, 16]) {
+            let location = frame.location.as_ref().unwrap();
+            assert!(location.file.ends_with("stack.c"), "{frame}");
+            assert_eq!(location.line, line, "{frame}");
+        }
+    }
+
+    #[test]
+    fn mismatched_build_id() {
+        let call_site = CallSite {
+            address: 0,
+            module: Some("/proc/self/exe".to_owned()),
+            build_id: Some(vec![0; 20]),
+            offset: 0x1000,
+        };
+        assert_eq!(Symbolizer::new().symbolize(&call_site), None);
+        let unknown = CallSite::default();
+        assert_eq!(Symbolizer::new().symbolize(&unknown), None);
+    }
+}
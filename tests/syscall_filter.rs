@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use boubo_trace::syscall::filter::{FilterParseError, SyscallFilter};
+
+    fn parse(expr: &str) -> SyscallFilter {
+        expr.parse().unwrap()
+    }
+
+    #[test]
+    fn names_and_numbers() {
+        let filter = parse("openat,close,1");
+        assert!(filter.matches(libc::SYS_openat as u64));
+        assert!(filter.matches(libc::SYS_close as u64));
+        assert!(filter.matches(libc::SYS_write as u64));
+        assert!(!filter.matches(libc::SYS_read as u64));
+        assert_eq!(parse("trace=openat,close,1"), filter);
+        assert_eq!(
+            filter.syscall_numbers().collect::<Vec<_>>(),
+            vec![1, libc::SYS_close as u64, libc::SYS_openat as u64]
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let filter = parse("%network");
+        assert!(filter.matches(libc::SYS_socket as u64));
+        assert!(filter.matches(libc::SYS_recvfrom as u64));
+        assert!(!filter.matches(libc::SYS_openat as u64));
+
+        let filter = parse("%file");
+        assert!(filter.matches(libc::SYS_openat as u64));
+        assert!(filter.matches(libc::SYS_execve as u64));
+        assert!(!filter.matches(libc::SYS_read as u64));
+    }
+
+    #[test]
+    fn negation() {
+        let filter = parse("!read,write");
+        assert!(!filter.matches(libc::SYS_read as u64));
+        assert!(!filter.matches(libc::SYS_write as u64));
+        assert!(filter.matches(libc::SYS_openat as u64));
+        assert!(parse("!none").is_all());
+        assert_eq!(parse("none"), SyscallFilter::none());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("".parse::<SyscallFilter>(), Err(FilterParseError::Empty));
+        assert_eq!(
+            "openat,nonsense".parse::<SyscallFilter>(),
+            Err(FilterParseError::UnknownSyscall("nonsense".to_owned()))
+        );
+        assert_eq!(
+            "%disk".parse::<SyscallFilter>(),
+            Err(FilterParseError::UnknownClass("disk".to_owned()))
+        );
+        assert_eq!(
+            "read,512".parse::<SyscallFilter>(),
+            Err(FilterParseError::InvalidNumber(512))
+        );
+    }
+
+    #[test]
+    fn display_parses_back() {
+        assert_eq!(parse("openat,close,1").to_string(), "write,close,openat");
+        assert_eq!(parse("!read,write").to_string(), "!read,write");
+        assert_eq!(parse("500").to_string(), "500");
+        assert_eq!(SyscallFilter::all().to_string(), "all");
+        assert_eq!(SyscallFilter::none().to_string(), "none");
+        for expr in ["%file", "!%network", "!500,read"] {
+            assert_eq!(parse(&parse(expr).to_string()), parse(expr), "{expr}");
+        }
+    }
+}
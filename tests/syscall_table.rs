@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use boubo_trace::syscall::{
+        SyscallInfoDiscriminants,
+        table::{self, ArgType, SYSCALLS},
+    };
+
+    #[test]
+    fn table_is_sorted_and_unique() {
+        assert!(SYSCALLS.windows(2).all(|w| w[0].number < w[1].number));
+        for signature in SYSCALLS {
+            assert!(
+                signature.args.len() <= 6,
+                "{} has too many args",
+                signature.name
+            );
+            assert_eq!(table::lookup_name(signature.name), Some(signature));
+        }
+    }
+
+    #[test]
+    fn lookup_matches_libc() {
+        let expected = [
+            (libc::SYS_read, "read"),
+            (libc::SYS_openat, "openat"),
+            (libc::SYS_execve, "execve"),
+            (libc::SYS_exit_group, "exit_group"),
+            (libc::SYS_clone3, "clone3"),
+            (libc::SYS_fchmodat2, "fchmodat2"),
+        ];
+        for (number, name) in expected {
+            assert_eq!(table::syscall_name(number as u64), Some(name));
+        }
+        assert_eq!(table::syscall_name(400), None);
+        assert_eq!(
+            table::lookup(libc::SYS_openat as u64).unwrap().args[1],
+            ArgType::Path
+        );
+    }
+
+    #[test]
+    fn discriminants_use_kernel_names() {
+        assert_eq!(
+            SyscallInfoDiscriminants::ExitGroup.to_string(),
+            "exit_group"
+        );
+        assert_eq!(SyscallInfoDiscriminants::Openat.to_string(), "openat");
+    }
+}
@@ -0,0 +1,270 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use boubo_trace::{
+        syscall::{
+            CallSite, Syscall, SyscallInfo, SyscallInfoDiscriminants, SyscallTime, TraceData,
+            capture::{CaptureLimits, CapturedBytes},
+            flags::{CloneFlags, FileMode, OpenFlags, SocketType},
+            inject::DelayPoint,
+            parse_error::TraceEvent,
+            table::ArgValue,
+        },
+        trace_file::{self, FORMAT_VERSION, MAGIC, TraceFile, TraceFileError, TraceMetadata},
+    };
+    use nix::{errno::Errno, sys::socket::AddressFamily};
+
+    use crate::common::{bytes, record, time};
+
+    // traces saved by the builds which introduced their versions, they have to stay readable,
+    // version 0 is `test_programs/open.c` traced by the build before the header
+    const TRACE_V0: &[u8] = include_bytes!("data/trace_v0.boubo");
+    const TRACE_V1: &[u8] = include_bytes!("data/trace_v1.boubo");
+
+    fn realtime(entry: u64) -> SyscallTime {
+        SyscallTime {
+            realtime_entry: Some(Duration::from_secs(1_700_000_000)),
+            ..time(entry, 3)
+        }
+    }
+
+    fn syscall(syscall: SyscallInfo, entry: u64) -> TraceData {
+        TraceData::Syscall(Syscall {
+            cpu_time: 0.25,
+            call_site: CallSite {
+                address: 0x7f00_0000_1234,
+                module: Some("/usr/lib/libc.so.6".to_owned()),
+                build_id: Some(vec![0xab, 0xcd]),
+                offset: 0x1234,
+            },
+            stack: vec![CallSite::default()],
+            ..record(syscall, realtime(entry))
+        })
+    }
+
+    fn sample() -> Vec<TraceData> {
+        vec![
+            syscall(
+                SyscallInfo::Openat {
+                    dirfd: libc::AT_FDCWD,
+                    pathname: bytes(b"/etc/hosts\0"),
+                    flags: OpenFlags(libc::O_RDONLY | libc::O_CLOEXEC),
+                    mode: FileMode(0),
+                    opened_fd: 3,
+                },
+                10,
+            ),
+            syscall(
+                SyscallInfo::Read {
+                    fd: 3,
+                    read_bytes: CapturedBytes::new(b"127.0.0.1 localhost\n".to_vec(), 9),
+                    requested_count: 4096,
+                },
+                20,
+            ),
+            syscall(
+                SyscallInfo::Socket {
+                    domain: AddressFamily::Inet6,
+                    r#type: SocketType(libc::SOCK_STREAM | libc::SOCK_CLOEXEC),
+                    protocol: 0,
+                },
+                30,
+            ),
+            syscall(
+                SyscallInfo::Clone {
+                    flags: CloneFlags(0x0120_0011),
+                    stack: 0,
+                    parent_tid: 0,
+                    child_tid: 0x7ffd_0000,
+                    tls: 0,
+                },
+                40,
+            ),
+            syscall(
+                SyscallInfo::Unknown {
+                    id: 262,
+                    args: [3, 0x1000, 0x2000, 0x100, 0, 0],
+                    decoded_args: vec![
+                        ArgValue::Fd(3),
+                        ArgValue::Path(bytes(b"\0")),
+                        ArgValue::Pointer(0x2000),
+                        ArgValue::Flags(0x100),
+                    ],
+                    return_value: 0,
+                },
+                50,
+            ),
+            syscall(SyscallInfo::ExitGroup { status: 0 }, 60),
+            TraceData::Event(TraceEvent::SyscallError {
+                syscall: SyscallInfoDiscriminants::Openat,
+                error: Errno::ENOENT,
+                cpu_time: 0.5,
+                time: realtime(70),
+                rip: 0x7f00_0000_5678,
+            }),
+            TraceData::Event(TraceEvent::DelayInjected {
+                id: 0,
+                delay: Duration::from_millis(200),
+                at: DelayPoint::Exit,
+            }),
+            TraceData::Event(TraceEvent::Exec {
+                executable: b"/usr/bin/true".to_vec(),
+            }),
+            TraceData::Event(TraceEvent::ProcessExit(0)),
+        ]
+    }
+
+    /// Metadata saved in [`TRACE_V1`]
+    fn metadata() -> TraceMetadata {
+        TraceMetadata {
+            executable: b"/usr/bin/cat".to_vec(),
+            args: vec![b"/etc/hosts".to_vec()],
+            work_dir: b"/home/user".to_vec(),
+            env: vec![b"HOME=/home/user".to_vec(), b"LANG=C.UTF-8".to_vec()],
+            start_time: Duration::from_secs(1_700_000_000),
+            hostname: "build-host".to_owned(),
+            kernel_version: "6.8.0-45-generic".to_owned(),
+            arch: "x86_64".to_owned(),
+            skip_to_main: true,
+            skip_to_main_after_exec: false,
+            filter: "!read,write".to_owned(),
+            seccomp: true,
+            realtime_timestamps: false,
+            stack_depth: 4,
+            capture_limits: CaptureLimits::default(),
+            faults: vec!["openat:path=/etc/hosts:error=ENOENT".to_owned()],
+            delays: vec!["write:delay=200ms:at=exit".to_owned()],
+            tampers: vec!["getpid:return=42".to_owned()],
+            replay: Some(12),
+            tracer_args: vec!["boubo_tracer".to_owned(), "/usr/bin/cat".to_owned()],
+            exit_status: Some(0x100),
+        }
+    }
+
+    fn without_metadata(trace: Vec<TraceData>) -> TraceFile {
+        TraceFile {
+            metadata: None,
+            trace,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let file = TraceFile {
+            metadata: Some(metadata()),
+            trace: sample(),
+        };
+        let bytes = trace_file::to_bytes(&file).unwrap();
+        assert_eq!(bytes[..8], MAGIC);
+        assert_eq!(trace_file::version(&bytes), FORMAT_VERSION);
+        assert_eq!(trace_file::from_bytes(&bytes).unwrap(), file);
+    }
+
+    #[test]
+    fn reads_version_1() {
+        assert_eq!(trace_file::version(TRACE_V1), 1);
+        let file = trace_file::from_bytes(TRACE_V1).unwrap();
+        assert_eq!(file.metadata, Some(metadata()));
+        assert_eq!(file.trace, sample());
+    }
+
+    /// Record of a syscall of version 0, which had only its offset in the program
+    fn v0_syscall(syscall: SyscallInfo, cpu_time: f64, offset: u64) -> TraceData {
+        TraceData::Syscall(Syscall {
+            cpu_time,
+            call_site: CallSite {
+                offset,
+                ..CallSite::default()
+            },
+            ..record(syscall, SyscallTime::default())
+        })
+    }
+
+    #[test]
+    fn reads_files_without_header() {
+        assert_eq!(trace_file::version(TRACE_V0), 0);
+        let file = trace_file::from_bytes(TRACE_V0).unwrap();
+        assert_eq!(file.metadata, None);
+        assert_eq!(
+            file.trace,
+            [
+                v0_syscall(
+                    SyscallInfo::Openat {
+                        dirfd: libc::AT_FDCWD,
+                        pathname: bytes(b"/tmp/test.txt\0"),
+                        flags: OpenFlags(libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC),
+                        mode: FileMode(libc::S_IRWXU),
+                        opened_fd: 3,
+                    },
+                    0.0,
+                    1_015_825,
+                ),
+                v0_syscall(
+                    SyscallInfo::Write {
+                        fd: 3,
+                        to_write: bytes(b"Hello world!\n"),
+                        written_count: 13,
+                    },
+                    7.48,
+                    1_016_656,
+                ),
+                v0_syscall(SyscallInfo::Close { fd: 3 }, 7.48, 1_018_352),
+                // that build read the path from the wrong register
+                v0_syscall(
+                    SyscallInfo::Unlink {
+                        pathname: bytes(b"Hello world!\n\0"),
+                    },
+                    7.48,
+                    1_022_743,
+                ),
+                TraceData::Event(TraceEvent::SyscallError {
+                    syscall: SyscallInfoDiscriminants::Openat,
+                    error: Errno::ENOENT,
+                    cpu_time: 7.48,
+                    time: SyscallTime::default(),
+                    rip: 0x7fab_726b_3011,
+                }),
+                v0_syscall(SyscallInfo::ExitGroup { status: 0 }, 7.48, 869_385),
+                TraceData::Event(TraceEvent::ProcessExit(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrades_old_versions() {
+        for old in [TRACE_V0, TRACE_V1] {
+            let file = trace_file::from_bytes(old).unwrap();
+            let upgraded = trace_file::upgrade(old).unwrap();
+            assert_eq!(trace_file::version(&upgraded), FORMAT_VERSION);
+            assert_eq!(trace_file::from_bytes(&upgraded).unwrap(), file);
+        }
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut bytes = trace_file::to_bytes(&without_metadata(sample())).unwrap();
+        bytes[8..12].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let err = trace_file::from_bytes(&bytes).unwrap_err();
+        assert!(
+            matches!(err, TraceFileError::UnsupportedVersion(version) if version == FORMAT_VERSION + 1),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let err = trace_file::from_bytes(b"#!/bin/sh\necho hello\n").unwrap_err();
+        assert!(matches!(err, TraceFileError::NotATrace(_)), "{err:?}");
+
+        let mut bytes = trace_file::to_bytes(&without_metadata(sample())).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        let err = trace_file::from_bytes(&bytes).unwrap_err();
+        assert!(
+            matches!(err, TraceFileError::Invalid { version, .. } if version == FORMAT_VERSION),
+            "{err:?}"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        os::unix::process::ExitStatusExt,
+        process::{Command, ExitStatus, Stdio},
+    };
+
+    use boubo_trace::{
+        syscall::{SyscallIterOpts, TraceData, capture::CaptureLimits, parse_error::TraceEvent},
+        tracer::Tracer,
+    };
+    use nix::sys::signal::Signal;
+
+    #[test]
+    fn redirect_and_env() {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", "read line; echo \"$VALUE $line\"; exit 7"]);
+        let mut child = Tracer::command(cmd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .env("VALUE", "from env")
+            .options(SyscallIterOpts::default().filter("read,write".parse().unwrap()))
+            .spawn()
+            .unwrap();
+        let mut stdin = child.take_stdin().unwrap();
+        stdin.write_all(b"from stdin\n").unwrap();
+        drop(stdin);
+        let mut stdout = child.take_stdout().unwrap();
+
+        let trace = child.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            trace.last(),
+            Some(&TraceData::Event(TraceEvent::ProcessExit(7)))
+        );
+        assert_eq!(child.exit_status().unwrap().code(), Some(7));
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "from env from stdin\n");
+    }
+
+    #[test]
+    fn killed() {
+        let mut cmd = Command::new("/bin/sleep");
+        cmd.arg("10");
+        let mut child = Tracer::command(cmd)
+            .options(SyscallIterOpts::default().skip_to_main(false))
+            .spawn()
+            .unwrap();
+        assert!(child.exit_status().is_none());
+        child.next().unwrap().unwrap();
+        child.kill().unwrap();
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", "exit 3"])
+            .current_dir("/tmp")
+            .env("BOUBO_METADATA", "1");
+        let mut child = Tracer::command(cmd)
+            .options(SyscallIterOpts::default().filter("!read,write".parse().unwrap()))
+            .spawn()
+            .unwrap();
+        assert_eq!(child.metadata().exit_status, None);
+        child.by_ref().for_each(drop);
+
+        let metadata = child.metadata();
+        let executable = std::fs::canonicalize("/bin/sh").unwrap();
+        assert_eq!(
+            metadata.executable,
+            executable.as_os_str().as_encoded_bytes()
+        );
+        assert_eq!(metadata.args, [b"-c".to_vec(), b"exit 3".to_vec()]);
+        assert_eq!(metadata.work_dir, b"/tmp");
+        assert!(metadata.env.contains(&b"BOUBO_METADATA=1".to_vec()));
+        assert_eq!(metadata.capture_limits, CaptureLimits::default());
+        assert_eq!(metadata.filter, "!read,write");
+        assert!(metadata.skip_to_main);
+        assert_eq!(metadata.arch, std::env::consts::ARCH);
+        assert!(!metadata.kernel_version.is_empty());
+        let status = ExitStatus::from_raw(metadata.exit_status.unwrap());
+        assert_eq!(status.code(), Some(3));
+    }
+}
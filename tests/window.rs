@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use boubo_trace::{
+        syscall::{SyscallIterOpts, TraceData, parse_error::TraceError},
+        tracee::{maps::find_function, window::TraceWindow},
+    };
+    use elf::{ElfBytes, endian::AnyEndian};
+
+    use crate::common::{make, trace, tracer};
+
+    /// Names of syscalls traced in `window.exec` with `windows`
+    fn trace_windows(windows: Vec<TraceWindow>) -> Vec<&'static str> {
+        let opts = windows
+            .into_iter()
+            .fold(SyscallIterOpts::default(), SyscallIterOpts::window);
+        trace(Command::new("./window.exec"), opts)
+            .into_iter()
+            .filter_map(|data| match data {
+                TraceData::Syscall(syscall) => Some(syscall.syscall),
+                TraceData::Event(_) => None,
+            })
+            .map(|syscall| syscall.name().unwrap())
+            .collect()
+    }
+
+    fn function(name: &str) -> TraceWindow {
+        TraceWindow::Function(name.to_owned())
+    }
+
+    #[test]
+    fn function_window() {
+        assert_eq!(trace_windows(vec![function("handler_a")]), ["getppid"; 3]);
+    }
+
+    #[test]
+    fn software_breakpoints() {
+        // more windows than debug registers
+        let windows = [
+            "handler_a",
+            "handler_b",
+            "handler_c",
+            "handler_d",
+            "handler_e",
+        ];
+        let trace = trace_windows(windows.into_iter().map(function).collect());
+        assert_eq!(trace, ["getppid"; 7]);
+    }
+
+    #[test]
+    fn recursive_window() {
+        // the window ends with the return of the outermost call
+        assert_eq!(trace_windows(vec![function("recurse")]), ["getppid"; 3]);
+    }
+
+    #[test]
+    fn address_window() {
+        make();
+        let file = std::fs::read("test_programs/build/window.exec").unwrap();
+        let elf = ElfBytes::<AnyEndian>::minimal_parse(&file).unwrap();
+        let window = TraceWindow::Addresses {
+            start: find_function(&elf, "handler_b").unwrap(),
+            end: find_function(&elf, "handler_d").unwrap(),
+        };
+        assert_eq!(trace_windows(vec![window]), ["getppid"; 2]);
+    }
+
+    #[test]
+    fn no_software_breakpoints_in_threads() {
+        // the debug registers are taken by the windows, the end of a window needs `int3`
+        let opts = ["handler_a", "handler_b", "handler_c", "handler_d"]
+            .into_iter()
+            .map(function)
+            .fold(SyscallIterOpts::default(), SyscallIterOpts::window);
+        let child = tracer(Command::new("./window_threads.exec"))
+            .options(opts)
+            .spawn()
+            .unwrap();
+        let err = child.collect::<Result<Vec<_>, _>>().unwrap_err();
+        assert!(matches!(err, TraceError::BreakpointInThreads(_)), "{err:?}");
+    }
+}